@@ -0,0 +1,6 @@
+export type JsonPatchOperation = {
+  op: "add" | "remove" | "replace" | "change" | "move" | "copy" | "test";
+  path: string;
+  from?: string;
+  value?: unknown;
+};
@@ -4,19 +4,98 @@ export type Fields = Record<string, FieldMeta>;
 export type TableMeta = {
   fields: Fields;
   comment?: string;
+  indexes?: Indexes;
+  view?: true;
+  schemaless?: true;
+  recordId?: FieldMeta;
+  referencedBy?: ReferenceMeta[];
+  events?: Events;
+  changefeed?: ChangefeedMeta;
 };
 
+export type ChangefeedMeta = {
+  expiry: string;
+  includeOriginal: boolean;
+};
+
+export type Events = Record<string, EventMeta>;
+
+export type EventMeta = {
+  when: string;
+  then: string[];
+  comment?: string;
+};
+
+export type ReferenceMeta = {
+  table: string;
+  field: string;
+  onDelete: string;
+};
+
+export type Indexes = Record<string, IndexMeta>;
+
+export type IndexMeta = {
+  columns: string[];
+  comment?: string;
+} & (
+  | { kind: "normal" | "unique" }
+  | { kind: "search"; analyzer: string; highlights: boolean }
+  | { kind: "mtree" | "hnsw"; dimension: number; distance: string }
+);
+
 export type FieldMeta = {
   comment?: string;
   type: FieldType;
   hasDefault?: true;
+  default?: string;
+  defaultValue?: JsonValue;
+  readonly?: true;
+  flexible?: true;
+  value?: string;
+  computed?: true;
+  assert?: string;
+  permissions?: FieldPermissions;
+  onDelete?: string;
 };
 
+export type FieldPermissions = {
+  select: Permission;
+  create: Permission;
+  update: Permission;
+};
+
+export type Permission = "FULL" | "NONE" | `WHERE ${string}`;
+
+export type FunctionMeta = {
+  args: FunctionArg[];
+  returns?: FieldType;
+  comment?: string;
+};
+
+export type FunctionArg = {
+  name: string;
+  type: FieldType;
+};
+
+export type ParamMeta = {
+  type: FieldType;
+  comment?: string;
+};
+
+export type AccessMeta = {
+  signup?: Fields;
+  signin?: Fields;
+  comment?: string;
+};
+
+export type JsonValue = null | boolean | number | string | JsonValue[] | { [key: string]: JsonValue };
+
 export type FieldType =
   | FieldTypes.Simple
   | FieldTypes.Option
   | FieldTypes.Object
   | FieldTypes.Record
+  | FieldTypes.References
   | FieldTypes.Array
   | FieldTypes.Union
   | FieldTypes.StringEnumUnion
@@ -45,6 +124,11 @@ export namespace FieldTypes {
     tables: string[];
   };
 
+  export type References = {
+    name: "references";
+    tables: string[];
+  };
+
   export type Array = {
     name: "array";
     item: FieldType;
@@ -89,3 +173,24 @@ export namespace FieldTypes {
 export type TableMetaFromDb = TableMeta & {
   id: string;
 };
+
+export type FunctionMetaFromDb = FunctionMeta & {
+  id: string;
+};
+
+export type ParamMetaFromDb = ParamMeta & {
+  id: string;
+};
+
+export type AccessMetaFromDb = AccessMeta & {
+  id: string;
+};
+
+export type TableMetaSnapshotFromDb = {
+  id: string;
+  hash: string;
+  createdAt: string;
+  version: string;
+  tables: Tables;
+  diff: JsonPatchOperation[];
+};
@@ -1,6 +1,10 @@
+use std::collections::BTreeMap;
+
 use clap::Parser;
 use serde::Deserialize;
 
+use crate::outputs::plugin::{PluginSpec, parse_plugin_spec};
+
 /// A simple typescript definition generator for SurrealDB
 #[derive(Parser, Debug, Deserialize)]
 #[command(author, version, about, long_about = None)]
@@ -29,6 +33,21 @@ pub struct Config {
     #[arg(short, long)]
     pub database: Option<String>,
 
+    /// Glob patterns of the tables to generate outputs for, every table is included if empty
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Glob patterns of the tables to leave out of the outputs
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// Glob patterns of the fields to leave out of the outputs, in the form of 'table.field'
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    pub exclude_fields: Vec<String>,
+
     /// Treat record types as FETCHED version of the linked table
     #[arg(short, long)]
     #[serde(default)]
@@ -39,6 +58,16 @@ pub struct Config {
     #[serde(default = "default_target_sdk")]
     pub target_sdk: bool,
 
+    /// The value type of the index signature added to the types of schemaless tables and flexible objects
+    #[arg(long, default_value_t = default_index_signature_type())]
+    #[serde(default = "default_index_signature_type")]
+    pub index_signature_type: String,
+
+    /// Keep the types of schemaless tables and flexible objects closed, without an index signature
+    #[arg(long)]
+    #[serde(default)]
+    pub no_index_signatures: bool,
+
     /// Store generated table and field metadata into the database
     #[arg(short, long)]
     #[serde(default)]
@@ -49,6 +78,38 @@ pub struct Config {
     #[serde(default = "default_metadata_table")]
     pub metadata_table_name: String,
 
+    /// Name of the table to keep a snapshot of the metadata in on every change, no history is kept if not set
+    #[arg(long)]
+    pub metadata_history_table_name: Option<String>,
+
+    /// Define the fields of the metadata table, making it SCHEMAFULL
+    #[arg(long)]
+    #[serde(default)]
+    pub metadata_schemafull: bool,
+
+    /// The select permission of the metadata tables: FULL, NONE or a WHERE clause, like "WHERE $auth.admin = true"
+    #[arg(long, default_value_t = default_metadata_select_permission())]
+    #[serde(default = "default_metadata_select_permission")]
+    pub metadata_select_permission: String,
+
+    /// The namespace to store the metadata in, the 'namespace' option is used if not set
+    #[arg(long)]
+    pub metadata_namespace: Option<String>,
+
+    /// The database to store the metadata in, the 'database' option is used if not set
+    #[arg(long)]
+    pub metadata_database: Option<String>,
+
+    /// Glob patterns of the tables to leave out of the stored metadata, while keeping them in the outputs
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default)]
+    pub metadata_exclude: Vec<String>,
+
+    /// Leave the comments of the definitions out of the stored metadata
+    #[arg(long)]
+    #[serde(default)]
+    pub metadata_strip_comments: bool,
+
     /// Skip adding the table meta descriptors to the output ts file
     #[arg(long)]
     #[serde(default)]
@@ -64,6 +125,39 @@ pub struct Config {
     #[serde(default = "default_output")]
     pub output: String,
 
+    /// Path where the intermediate representation JSON file will be written
+    #[arg(long)]
+    pub emit_ir: Option<String>,
+
+    /// Path to an intermediate representation JSON file to use instead of the database
+    #[arg(long)]
+    pub from_ir: Option<String>,
+
+    /// Path to a SurrealQL file to read the schema from instead of the database (can be repeated)
+    #[arg(long)]
+    #[serde(default)]
+    pub from_surql: Vec<String>,
+
+    /// External generator that can be selected with 'emit', in the form of 'name=path/to/executable' (can be repeated)
+    #[arg(long = "plugin", value_name = "NAME=PATH", value_parser = parse_plugin_spec)]
+    #[serde(default)]
+    pub plugins: Vec<PluginSpec>,
+
+    /// Comma separated list of the outputs to generate: ts, zod, jsonschema, ir or a plugin name
+    #[arg(short, long, value_delimiter = ',', default_value = "ts")]
+    #[serde(default = "default_emit")]
+    pub emit: Vec<String>,
+
+    /// Options of the outputs, keyed by output name (only settable from the config file)
+    #[arg(skip)]
+    #[serde(default)]
+    pub outputs: BTreeMap<String, serde_json::Value>,
+
+    /// Print the SurrealQL statements and the files that would be written, without changing the database or the filesystem
+    #[arg(long)]
+    #[serde(default)]
+    pub dry_run: bool,
+
     /// Path to the configuration JSON file
     #[arg(short, long)]
     pub config_file_path: Option<String>,
@@ -85,25 +179,37 @@ fn default_target_sdk() -> bool {
     true
 }
 
+fn default_index_signature_type() -> String {
+    "unknown".to_string()
+}
+
 fn default_metadata_table() -> String {
     "table_meta".to_string()
 }
 
+fn default_metadata_select_permission() -> String {
+    "FULL".to_string()
+}
+
 fn default_output() -> String {
     "db.ts".to_string()
 }
 
+fn default_emit() -> Vec<String> {
+    vec!["ts".to_string()]
+}
+
 use std::fs::File;
 use std::io::BufReader;
 
-pub(crate) fn get_config() -> anyhow::Result<Config> {
+pub fn get_config() -> anyhow::Result<Config> {
     let config = Config::parse();
 
-    return if let Some(path) = &config.config_file_path {
+    if let Some(path) = &config.config_file_path {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
         Ok(serde_json::from_reader(reader)?)
     } else {
         Ok(config)
-    };
+    }
 }
@@ -0,0 +1,346 @@
+use std::iter;
+
+use crate::outputs::db::get_metadata_table_names;
+use crate::{FieldMetas, FieldType, TableMetas, config::Config};
+
+/// Removes the tables and fields from the metas, that are not needed in the outputs
+#[derive(Debug, Default)]
+pub struct TableFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    exclude_fields: Vec<(String, String)>,
+    metadata_tables: Vec<String>,
+}
+
+impl TableFilter {
+    pub fn new(config: &Config) -> anyhow::Result<Self> {
+        let exclude_fields = config
+            .exclude_fields
+            .iter()
+            .map(|pattern| match pattern.split_once('.') {
+                Some((table, field)) => Ok((table.to_string(), field.to_string())),
+                None => Err(anyhow::anyhow!(
+                    "Invalid field pattern '{pattern}', expected the form 'table.field'."
+                )),
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            include: config.include.clone(),
+            exclude: config.exclude.clone(),
+            exclude_fields,
+            // The metadata stored by surreal-ts is not part of the user's schema
+            metadata_tables: match config.store_meta_in_db {
+                true => get_metadata_table_names(config),
+                false => vec![],
+            },
+        })
+    }
+
+    pub fn is_table_included(&self, table: &str) -> bool {
+        if self.metadata_tables.iter().any(|name| name == table) {
+            return false;
+        }
+
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| matches_glob(pattern, table));
+
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| matches_glob(pattern, table))
+    }
+
+    /// Checks if the field at the path, or one of the objects containing it, is excluded
+    fn is_field_excluded(&self, table: &str, path: &str) -> bool {
+        let patterns = self.get_field_patterns(table);
+
+        path.match_indices('.')
+            .map(|(end, _)| &path[..end])
+            .chain(iter::once(path))
+            .any(|prefix| patterns.iter().any(|pattern| matches_glob(pattern, prefix)))
+    }
+
+    /// Drops the tables and fields that are filtered out. Record links pointing to
+    /// removed tables are kept, the outputs type them as plain record ids.
+    pub fn apply(&self, tables: TableMetas) -> TableMetas {
+        tables
+            .into_iter()
+            .filter(|(name, _)| self.is_table_included(name))
+            .map(|(name, mut meta)| {
+                let patterns = self.get_field_patterns(&name);
+
+                if !patterns.is_empty() {
+                    remove_fields(&mut meta.fields, &patterns, "");
+                }
+
+                // References from the removed tables and fields would point nowhere
+                meta.referenced_by.retain(|reference| {
+                    self.is_table_included(&reference.table)
+                        && !self.is_field_excluded(&reference.table, &reference.field)
+                });
+
+                (name, meta)
+            })
+            .collect()
+    }
+
+    fn get_field_patterns(&self, table: &str) -> Vec<&str> {
+        self.exclude_fields
+            .iter()
+            .filter(|(pattern, _)| matches_glob(pattern, table))
+            .map(|(_, field)| field.as_str())
+            .collect()
+    }
+}
+
+fn remove_fields(fields: &mut FieldMetas, patterns: &[&str], prefix: &str) {
+    fields.retain(|name, _| {
+        let path = format!("{prefix}{name}");
+        !patterns.iter().any(|pattern| matches_glob(pattern, &path))
+    });
+
+    for (name, meta) in fields.iter_mut() {
+        if let Some(subfields) = get_subfields(&mut meta.r#type) {
+            remove_fields(subfields, patterns, &format!("{prefix}{name}."));
+        }
+    }
+}
+
+/// Gets the fields of an object type, looking through options and arrays
+pub(crate) fn get_subfields(r#type: &mut FieldType) -> Option<&mut FieldMetas> {
+    match r#type {
+        FieldType::Object { fields } => fields.as_mut(),
+        FieldType::Option { inner } => get_subfields(inner),
+        FieldType::Array { item } => get_subfields(item),
+        _ => None,
+    }
+}
+
+/// Matches the text against a glob pattern, where `*` matches any
+/// number of characters and `?` matches exactly one character
+pub fn matches_glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldMeta, ReferenceMeta, TableMeta};
+
+    fn table(fields: FieldMetas) -> TableMeta {
+        TableMeta {
+            fields,
+            ..Default::default()
+        }
+    }
+
+    fn object(fields: Vec<(&str, FieldMeta)>) -> FieldMeta {
+        let fields = fields
+            .into_iter()
+            .map(|(name, meta)| (name.to_string(), meta))
+            .collect();
+
+        FieldMeta::new(FieldType::Object {
+            fields: Some(fields),
+        })
+    }
+
+    fn string() -> FieldMeta {
+        FieldMeta::new(FieldType::String)
+    }
+
+    #[test]
+    fn star_matches_any_number_of_characters() {
+        assert!(matches_glob("audit_*", "audit_"));
+        assert!(matches_glob("audit_*", "audit_log"));
+        assert!(matches_glob("*_log", "audit_log"));
+        assert!(matches_glob("a*b*c", "axxbyyc"));
+        assert!(matches_glob("a*b", "abab"));
+        assert!(!matches_glob("audit_*", "audit"));
+        assert!(!matches_glob("a*b", "abac"));
+    }
+
+    #[test]
+    fn question_mark_matches_exactly_one_character() {
+        assert!(matches_glob("user?", "user1"));
+        assert!(!matches_glob("user?", "user"));
+        assert!(!matches_glob("user?", "user12"));
+        assert!(matches_glob("?*", "u"));
+    }
+
+    #[test]
+    fn empty_pattern_only_matches_empty_text() {
+        assert!(matches_glob("", ""));
+        assert!(!matches_glob("", "user"));
+        assert!(matches_glob("*", ""));
+        assert!(!matches_glob("?", ""));
+    }
+
+    #[test]
+    fn filters_tables_by_include_and_exclude_patterns() {
+        let filter = TableFilter {
+            include: vec!["user*".to_string(), "post".to_string()],
+            exclude: vec!["user_?".to_string()],
+            ..Default::default()
+        };
+
+        assert!(filter.is_table_included("user"));
+        assert!(filter.is_table_included("user_role"));
+        assert!(filter.is_table_included("post"));
+        assert!(!filter.is_table_included("user_1"));
+        assert!(!filter.is_table_included("comment"));
+    }
+
+    #[test]
+    fn skips_the_metadata_tables() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "store-meta-in-db": true,
+                "metadata-table-name": "schema_meta",
+                "metadata-history-table-name": "schema_history"
+            }"#,
+        )
+        .expect("The config is valid.");
+        let filter = TableFilter::new(&config).expect("The filter patterns are valid.");
+
+        let tables = filter.apply(
+            [
+                "schema_meta",
+                "schema_meta_functions",
+                "schema_meta_accesses",
+                "schema_history",
+                "user",
+            ]
+            .map(|name| (name.to_string(), table(FieldMetas::new())))
+            .into(),
+        );
+
+        assert_eq!(tables.keys().collect::<Vec<_>>(), ["user"]);
+    }
+
+    #[test]
+    fn keeps_the_metadata_tables_when_not_storing_metadata() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "metadata-table-name": "schema_meta",
+                "metadata-history-table-name": "schema_history"
+            }"#,
+        )
+        .expect("The config is valid.");
+        let filter = TableFilter::new(&config).expect("The filter patterns are valid.");
+
+        let tables = filter.apply(
+            ["schema_meta", "schema_history", "user"]
+                .map(|name| (name.to_string(), table(FieldMetas::new())))
+                .into(),
+        );
+
+        assert_eq!(
+            tables.keys().collect::<Vec<_>>(),
+            ["schema_history", "schema_meta", "user"]
+        );
+    }
+
+    #[test]
+    fn removes_nested_fields_by_path() {
+        let filter = TableFilter {
+            exclude_fields: vec![
+                ("user".to_string(), "profile.secret.*".to_string()),
+                ("u*".to_string(), "password_hash".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        let secret = object(vec![("key", string()), ("salt", string())]);
+        let profile = object(vec![("name", string()), ("secret", secret)]);
+        let user = table(
+            [
+                ("password_hash".to_string(), string()),
+                ("profile".to_string(), profile),
+            ]
+            .into(),
+        );
+        let post = table([("password_hash".to_string(), string())].into());
+
+        let tables = filter.apply([("user".to_string(), user), ("post".to_string(), post)].into());
+
+        let user = &tables["user"].fields;
+        assert!(!user.contains_key("password_hash"));
+
+        let Some(profile) = get_subfields(&mut user["profile"].r#type.clone()).cloned() else {
+            panic!("The profile field is an object.");
+        };
+        assert!(profile.contains_key("name"));
+
+        let Some(secret) = get_subfields(&mut profile["secret"].r#type.clone()).cloned() else {
+            panic!("The secret field is an object.");
+        };
+        assert!(secret.is_empty());
+
+        assert!(tables["post"].fields.contains_key("password_hash"));
+    }
+
+    #[test]
+    fn removes_the_references_of_filtered_out_tables_and_fields() {
+        let filter = TableFilter {
+            exclude: vec!["audit_*".to_string()],
+            exclude_fields: vec![("post".to_string(), "meta".to_string())],
+            ..Default::default()
+        };
+
+        let reference = |table: &str, field: &str| ReferenceMeta {
+            table: table.to_string(),
+            field: field.to_string(),
+            on_delete: "IGNORE".to_string(),
+        };
+        let user = TableMeta {
+            referenced_by: vec![
+                reference("post", "author"),
+                reference("post", "meta.editor"),
+                reference("audit_log", "user"),
+            ],
+            ..Default::default()
+        };
+
+        let tables = filter.apply([("user".to_string(), user)].into());
+
+        let fields: Vec<_> = tables["user"]
+            .referenced_by
+            .iter()
+            .map(|reference| format!("{}.{}", reference.table, reference.field))
+            .collect();
+        assert_eq!(fields, ["post.author"]);
+    }
+}
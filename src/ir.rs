@@ -0,0 +1,42 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Schema;
+
+/// Version of the intermediate representation format, bumped on every breaking change
+pub const IR_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ir {
+    pub version: u32,
+    #[serde(flatten)]
+    pub schema: Schema,
+}
+
+impl Ir {
+    pub fn new(schema: Schema) -> Self {
+        Self {
+            version: IR_VERSION,
+            schema,
+        }
+    }
+}
+
+pub fn read_ir(path: &str) -> anyhow::Result<Schema> {
+    println!("Reading intermediate representation file...");
+
+    let reader = BufReader::new(File::open(path)?);
+    let ir: Ir = serde_json::from_reader(reader)?;
+
+    if ir.version != IR_VERSION {
+        anyhow::bail!(
+            "The intermediate representation file '{path}' has version {}, but only version {IR_VERSION} is supported.",
+            ir.version
+        );
+    }
+
+    Ok(ir.schema)
+}
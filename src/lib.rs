@@ -0,0 +1,1364 @@
+// A simple to use typescript type definition generator for SurrealDB
+// Copyright (C) 2023  Horváth Bálint
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see https://www.gnu.org/licenses/.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::statements::{
+    DefineAccessStatement, DefineEventStatement, DefineFieldStatement, DefineFunctionStatement,
+    DefineIndexStatement, DefineParamStatement, DefineTableStatement, IfelseStatement,
+    InsertStatement, RelateStatement, SelectStatement,
+};
+use surrealdb::sql::{self, Kind, Query};
+use surrealdb::{Connection, Surreal};
+
+use filter::TableFilter;
+use sources::{SchemaDefinition, SchemaSource, TableDefinition, db::DbSource};
+use surrealdb::syn::parser::{Parser, ParserSettings};
+
+pub mod config;
+pub mod filter;
+pub mod ir;
+pub mod outputs;
+pub mod sources;
+
+/// Reads the table and field definitions of the database selected on the given connection,
+/// leaving out the tables and fields dropped by the filter
+pub async fn get_tables_metas_for_db<C: Connection>(
+    db: &Surreal<C>,
+    filter: &TableFilter,
+) -> anyhow::Result<TableMetas> {
+    let tables = DbSource::new(db).get_schema().await?.tables;
+
+    Ok(filter.apply(tables))
+}
+
+/// Converts every definition read by a schema source into metas
+pub fn get_schema(definition: SchemaDefinition) -> Schema {
+    let tables = get_tables_metas(definition.tables);
+    let params = get_param_metas(definition.params);
+    let accesses = get_access_metas(definition.accesses, &tables, &params);
+
+    Schema {
+        tables,
+        functions: get_function_metas(definition.functions),
+        params,
+        accesses,
+    }
+}
+
+/// Converts the definitions read by a schema source into table metas
+pub fn get_tables_metas(definitions: Vec<TableDefinition>) -> TableMetas {
+    let mut tables = BTreeMap::new();
+
+    // The fields of views come from the tables they select from, so those are processed first
+    let (mut views, definitions): (Vec<_>, Vec<_>) = definitions
+        .into_iter()
+        .partition(|definition| definition.table.view.is_some());
+
+    for definition in definitions {
+        insert_table_meta(&mut tables, definition);
+    }
+
+    while !views.is_empty() {
+        let pending = views
+            .iter()
+            .map(|definition| definition.table.name.to_string())
+            .collect::<Vec<_>>();
+
+        // A view selecting from another view waits for it, unless they select from each other
+        let (ready, waiting): (Vec<_>, Vec<_>) = views.into_iter().partition(|definition| {
+            get_view_sources(&definition.table)
+                .iter()
+                .all(|source| !pending.contains(source))
+        });
+
+        let (ready, waiting) = match ready.is_empty() {
+            true => (waiting, vec![]),
+            false => (ready, waiting),
+        };
+
+        for definition in ready {
+            insert_table_meta(&mut tables, definition);
+        }
+
+        views = waiting;
+    }
+
+    let mut references = vec![];
+    for (name, meta) in tables.iter().filter(|(_, meta)| !meta.view) {
+        collect_references(name, &meta.fields, "", &mut references);
+    }
+
+    for (linked_table, reference) in references {
+        if let Some(meta) = tables.get_mut(&linked_table) {
+            meta.referenced_by.push(reference);
+        }
+    }
+
+    tables
+}
+
+fn insert_table_meta(tables: &mut TableMetas, definition: TableDefinition) {
+    let TableDefinition {
+        table,
+        fields,
+        indexes,
+        events,
+    } = definition;
+
+    println!("Processing table: {}", table.name);
+
+    let mut fields = match &table.view {
+        Some(view) => get_view_field_metas(view, tables),
+        None => get_field_metas(&fields, "".to_string()),
+    };
+
+    // The id is not an ordinary field, its definition only constrains the ids of the records
+    let record_id = fields.remove("id");
+
+    let table_meta = TableMeta {
+        fields,
+        comment: table.comment.map(|c| c.to_string()),
+        indexes: get_index_metas(&indexes),
+        view: table.view.is_some(),
+        // The records of views only have the fields of their projection
+        schemaless: !table.full && table.view.is_none(),
+        record_id,
+        referenced_by: vec![],
+        events: get_event_metas(&events),
+        changefeed: table.changefeed.map(|changefeed| ChangefeedMeta {
+            expiry: sql::Duration::from(changefeed.expiry).to_string(),
+            include_original: changefeed.store_diff,
+        }),
+    };
+
+    tables.insert(table.name.to_string(), table_meta);
+}
+
+/// Collects the REFERENCE fields of a table, paired with the names of the tables they link to
+fn collect_references(
+    table: &str,
+    fields: &FieldMetas,
+    prefix: &str,
+    references: &mut Vec<(String, ReferenceMeta)>,
+) {
+    for (name, meta) in fields {
+        let path = format!("{prefix}{name}");
+
+        if let Some(on_delete) = &meta.on_delete {
+            for linked_table in get_linked_tables(&meta.r#type) {
+                let reference = ReferenceMeta {
+                    table: table.to_string(),
+                    field: path.clone(),
+                    on_delete: on_delete.clone(),
+                };
+
+                references.push((linked_table, reference));
+            }
+        }
+
+        if let Some(subfields) = get_object_fields(&meta.r#type) {
+            collect_references(table, subfields, &format!("{path}."), references);
+        }
+    }
+}
+
+fn get_linked_tables(r#type: &FieldType) -> Vec<String> {
+    match r#type {
+        FieldType::Record { tables } => tables.clone(),
+        FieldType::Option { inner } => get_linked_tables(inner),
+        FieldType::Array { item } => get_linked_tables(item),
+        FieldType::Union(Union::Normal { variants }) => {
+            variants.iter().flat_map(get_linked_tables).collect()
+        }
+        _ => vec![],
+    }
+}
+
+fn get_object_fields(r#type: &FieldType) -> Option<&FieldMetas> {
+    match r#type {
+        FieldType::Object { fields } => fields.as_ref(),
+        FieldType::Option { inner } => get_object_fields(inner),
+        FieldType::Array { item } => get_object_fields(item),
+        _ => None,
+    }
+}
+
+fn parse_sql(sql: &str) -> anyhow::Result<Query> {
+    // Record references are still an experimental feature of SurrealDB
+    let settings = ParserSettings {
+        references_enabled: true,
+        ..Default::default()
+    };
+    let mut parser = Parser::new_with_settings(sql.as_bytes(), settings);
+    let mut stack = reblessive::Stack::new();
+
+    stack
+        .enter(|ctx| parser.parse_query(ctx))
+        .finish()
+        .map_err(|err| anyhow::anyhow!("Failed to parse SurrealQL: {}", err.render_on(sql)))
+}
+
+/// Everything the outputs are generated from
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    pub tables: TableMetas,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub functions: FunctionMetas,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub params: ParamMetas,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub accesses: AccessMetas,
+}
+
+pub type TableMetas = BTreeMap<String, TableMeta>;
+pub type FieldMetas = BTreeMap<String, FieldMeta>;
+pub type IndexMetas = BTreeMap<String, IndexMeta>;
+pub type EventMetas = BTreeMap<String, EventMeta>;
+pub type FunctionMetas = BTreeMap<String, FunctionMeta>;
+pub type ParamMetas = BTreeMap<String, ParamMeta>;
+pub type AccessMetas = BTreeMap<String, AccessMeta>;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TableMeta {
+    pub fields: FieldMetas,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub indexes: IndexMetas,
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub view: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub schemaless: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub record_id: Option<FieldMeta>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub referenced_by: Vec<ReferenceMeta>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub events: EventMetas,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub changefeed: Option<ChangefeedMeta>,
+}
+
+/// The CHANGEFEED clause of a table: how long its changes are kept, and whether the
+/// updates are recorded together with the patches leading back to the original record
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangefeedMeta {
+    pub expiry: String,
+    pub include_original: bool,
+}
+
+/// An event of a table, with the source text of its WHEN condition and THEN statements
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EventMeta {
+    pub when: String,
+    pub then: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub comment: Option<String>,
+}
+
+/// A REFERENCE field of another table, linking to the records of a table
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceMeta {
+    pub table: String,
+    pub field: String,
+    pub on_delete: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexMeta {
+    pub columns: Vec<String>,
+    #[serde(flatten)]
+    pub kind: IndexKind,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "kind")]
+pub enum IndexKind {
+    Normal,
+    Unique,
+    Search { analyzer: String, highlights: bool },
+    Mtree { dimension: u16, distance: String },
+    Hnsw { dimension: u16, distance: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldMeta {
+    pub r#type: FieldType,
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub has_default: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub default_value: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub readonly: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub flexible: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub computed: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub assert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub permissions: Option<FieldPermissions>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub on_delete: Option<String>,
+}
+
+impl FieldMeta {
+    /// Creates the meta of a field that has nothing but a type
+    pub fn new(r#type: FieldType) -> Self {
+        Self {
+            r#type,
+            has_default: false,
+            default: None,
+            default_value: None,
+            comment: None,
+            readonly: false,
+            flexible: false,
+            value: None,
+            computed: false,
+            assert: None,
+            permissions: None,
+            on_delete: None,
+        }
+    }
+
+    /// Whether the database sets the value of the field, when it is left out of the input
+    pub fn is_set_by_database(&self) -> bool {
+        self.has_default || self.computed
+    }
+
+    /// Whether the field can only be read, like the `references<>` fields computed by the database
+    pub fn is_output_only(&self) -> bool {
+        matches!(self.r#type, FieldType::References { .. })
+    }
+}
+
+/// A custom function, keyed by its name without the `fn::` prefix. It can return anything
+/// when no return type is declared, and the names of its arguments have no `$` prefix.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionMeta {
+    pub args: Vec<FunctionArg>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub returns: Option<FieldType>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionArg {
+    pub name: String,
+    pub r#type: FieldType,
+}
+
+/// A database parameter, keyed by its name without the `$` prefix. Only the type of
+/// its value is kept, since parameters can hold secrets that should not leak into the outputs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamMeta {
+    pub r#type: FieldType,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub comment: Option<String>,
+}
+
+/// A record access method of the database, with the variables the client has to send to
+/// sign up or sign in, which are not set if the access method has no such query. The variables
+/// are typed after the fields they are compared to or stored in.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessMeta {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub signup: Option<FieldMetas>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub signin: Option<FieldMetas>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub comment: Option<String>,
+}
+
+/// The permission of every operation on a field, as `FULL`, `NONE` or `WHERE <condition>`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldPermissions {
+    pub select: String,
+    pub create: String,
+    pub update: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "name")]
+pub enum FieldType {
+    Any,
+    Null,
+    Boolean,
+    String,
+    Number,
+    Decimal,
+    Duration,
+    Uuid,
+    Date,
+    Bytes,
+    Option { inner: Box<FieldType> },
+    Record { tables: Vec<String> },
+    References { tables: Vec<String> },
+    Array { item: Box<FieldType> },
+    Object { fields: Option<FieldMetas> },
+    Union(Union),
+    Literal(Literal),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(untagged)]
+pub enum Union {
+    Normal { variants: Vec<FieldType> },
+    Enum(Enum),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "enum")]
+pub enum Enum {
+    String { variants: Vec<String> },
+    Number { variants: Vec<f64> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "kind")]
+pub enum Literal {
+    String { value: String },
+    Number { value: f64 },
+    Array { items: Vec<FieldType> },
+}
+
+impl From<Literal> for FieldType {
+    fn from(literal: Literal) -> Self {
+        FieldType::Literal(literal)
+    }
+}
+
+fn get_function_metas(definitions: Vec<DefineFunctionStatement>) -> FunctionMetas {
+    let mut functions = BTreeMap::new();
+
+    for function in definitions {
+        let name = function.name.0;
+        println!("Processing function: fn::{name}");
+
+        let args = function
+            .args
+            .into_iter()
+            .map(|(arg, kind)| FunctionArg {
+                r#type: get_field_type(
+                    format!("fn::{name}(${arg})"),
+                    Some(kind),
+                    &mut iter::empty(),
+                ),
+                name: arg.0,
+            })
+            .collect();
+
+        let function_meta = FunctionMeta {
+            args,
+            returns: function
+                .returns
+                .map(|kind| get_field_type(format!("fn::{name}"), Some(kind), &mut iter::empty())),
+            comment: function.comment.map(|c| c.0),
+        };
+
+        functions.insert(name, function_meta);
+    }
+
+    functions
+}
+
+fn get_param_metas(definitions: Vec<DefineParamStatement>) -> ParamMetas {
+    let mut params = BTreeMap::new();
+
+    for param in definitions {
+        println!("Processing param: ${}", param.name.0);
+
+        let param_meta = ParamMeta {
+            r#type: get_value_type(&param.value),
+            comment: param.comment.map(|c| c.0),
+        };
+
+        params.insert(param.name.0, param_meta);
+    }
+
+    params
+}
+
+fn get_access_metas(
+    definitions: Vec<DefineAccessStatement>,
+    tables: &TableMetas,
+    params: &ParamMetas,
+) -> AccessMetas {
+    let mut accesses = BTreeMap::new();
+
+    for access in definitions {
+        // Only record access methods are used with variables sent by the client
+        let sql::AccessType::Record(record) = &access.kind else {
+            continue;
+        };
+
+        println!("Processing access: {}", access.name.0);
+
+        let get_variables = |query: &sql::Value| get_query_variables(query, tables, params);
+        let access_meta = AccessMeta {
+            signup: record.signup.as_ref().map(get_variables),
+            signin: record.signin.as_ref().map(get_variables),
+            comment: access.comment.map(|c| c.0),
+        };
+
+        accesses.insert(access.name.0, access_meta);
+    }
+
+    accesses
+}
+
+/// The params set by the database, that the client does not have to send
+const SYSTEM_PARAMS: [&str; 11] = [
+    "access", "after", "auth", "before", "event", "input", "parent", "session", "this", "token",
+    "value",
+];
+
+fn get_query_variables(query: &sql::Value, tables: &TableMetas, params: &ParamMetas) -> FieldMetas {
+    let mut assignments = BTreeMap::new();
+    collect_assignments(query, None, &mut assignments);
+
+    let mut names = BTreeSet::new();
+    collect_params(query, &mut names);
+
+    names
+        .into_iter()
+        .filter(|name| !SYSTEM_PARAMS.contains(&name.as_str()) && !params.contains_key(name))
+        .map(|name| {
+            let field_type = assignments
+                .get(&name)
+                .and_then(|(table, path): &(String, String)| {
+                    find_field_type(&tables.get(table)?.fields, path)
+                })
+                .unwrap_or(FieldType::Any);
+
+            (name, FieldMeta::new(field_type))
+        })
+        .collect()
+}
+
+/// Collects the params, that are compared to or stored in a field of a table,
+/// together with the table and the path of that field
+fn collect_assignments(
+    value: &sql::Value,
+    table: Option<&str>,
+    assignments: &mut BTreeMap<String, (String, String)>,
+) {
+    let mut assign = |table: Option<&str>, path: String, value: &sql::Value| {
+        if let (Some(table), sql::Value::Param(param)) = (table, value) {
+            assignments
+                .entry(param.0.0.clone())
+                .or_insert((table.to_string(), path));
+        }
+    };
+
+    match value {
+        sql::Value::Subquery(subquery) => match &**subquery {
+            sql::Subquery::Create(stmt) => {
+                let table = get_statement_table(&stmt.what);
+
+                match &stmt.data {
+                    Some(sql::Data::SetExpression(items)) => {
+                        for (idiom, _, value) in items {
+                            assign(table, idiom.to_string(), value);
+                        }
+                    }
+                    Some(sql::Data::ContentExpression(sql::Value::Object(object))) => {
+                        for (name, value) in object.iter() {
+                            assign(table, name.clone(), value);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            sql::Subquery::Select(stmt) => {
+                if let Some(cond) = &stmt.cond {
+                    collect_assignments(&cond.0, get_statement_table(&stmt.what), assignments);
+                }
+            }
+            sql::Subquery::Value(value) => collect_assignments(value, table, assignments),
+            _ => {}
+        },
+        sql::Value::Expression(expression) => {
+            let sql::Expression::Binary { l, o, r } = &**expression else {
+                return;
+            };
+
+            match (l, o, r) {
+                (sql::Value::Idiom(idiom), sql::Operator::Equal | sql::Operator::Exact, param)
+                | (param, sql::Operator::Equal | sql::Operator::Exact, sql::Value::Idiom(idiom)) => {
+                    assign(table, idiom.to_string(), param)
+                }
+                _ => {
+                    collect_assignments(l, table, assignments);
+                    collect_assignments(r, table, assignments);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn get_statement_table(what: &sql::Values) -> Option<&str> {
+    match what.0.first()? {
+        sql::Value::Table(table) => Some(&table.0),
+        _ => None,
+    }
+}
+
+fn find_field_type(fields: &FieldMetas, path: &str) -> Option<FieldType> {
+    let (name, rest) = match path.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path, None),
+    };
+
+    let mut field_type = &fields.get(name)?.r#type;
+    let Some(rest) = rest else {
+        return Some(field_type.clone());
+    };
+
+    while let FieldType::Option { inner } = field_type {
+        field_type = inner;
+    }
+
+    match field_type {
+        FieldType::Object {
+            fields: Some(fields),
+        } => find_field_type(fields, rest),
+        _ => None,
+    }
+}
+
+/// Resolves the fields of a view from the projection of its SELECT statement
+fn get_view_field_metas(view: &sql::View, tables: &TableMetas) -> FieldMetas {
+    let source = view
+        .what
+        .0
+        .first()
+        .and_then(|table| tables.get(&table.0))
+        .map(|table| table.fields.clone())
+        .unwrap_or_default();
+
+    let mut fields = BTreeMap::new();
+
+    for field in &view.expr.0 {
+        match field {
+            sql::Field::All => fields.extend(source.clone()),
+            sql::Field::Single { expr, alias } => {
+                let path = match (alias, expr) {
+                    (Some(alias), _) => get_idiom_path(alias),
+                    (None, sql::Value::Idiom(idiom)) => get_idiom_path(idiom),
+                    (None, sql::Value::Function(function)) => {
+                        vec![function.name().unwrap_or_default().to_string()]
+                    }
+                    (None, expr) => vec![expr.to_string()],
+                };
+                let field = FieldMeta::new(get_view_field_type(expr, &source));
+
+                insert_view_field(&mut fields, &path, field);
+            }
+            _ => {}
+        }
+    }
+
+    // The database computes every field of a view
+    for field in fields.values_mut() {
+        field.readonly = true;
+    }
+
+    fields
+}
+
+fn get_view_sources(table: &DefineTableStatement) -> Vec<String> {
+    table
+        .view
+        .iter()
+        .flat_map(|view| view.what.0.iter().map(|table| table.0.clone()))
+        .collect()
+}
+
+/// Splits a projected idiom like `profile.age` into the names of the nested fields it creates,
+/// keeping the idioms that are not plain field paths as a single name
+fn get_idiom_path(idiom: &sql::Idiom) -> Vec<String> {
+    let names = idiom
+        .0
+        .iter()
+        .map(|part| match part {
+            sql::Part::Field(name) => Some(name.0.clone()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+
+    names.unwrap_or_else(|| vec![idiom.to_string()])
+}
+
+fn insert_view_field(fields: &mut FieldMetas, path: &[String], field: FieldMeta) {
+    let [name, rest @ ..] = path else {
+        return;
+    };
+
+    if rest.is_empty() {
+        fields.insert(name.clone(), field);
+        return;
+    }
+
+    let parent = fields.entry(name.clone()).or_insert_with(|| {
+        FieldMeta::new(FieldType::Object {
+            fields: Some(FieldMetas::new()),
+        })
+    });
+
+    if let FieldType::Object {
+        fields: Some(fields),
+    } = &mut parent.r#type
+    {
+        insert_view_field(fields, rest, field);
+    }
+}
+
+fn get_view_field_type(expr: &sql::Value, source: &FieldMetas) -> FieldType {
+    let sql::Value::Function(function) = expr else {
+        return match expr {
+            sql::Value::Idiom(idiom) => {
+                find_field_type(source, &idiom.to_string()).unwrap_or(FieldType::Any)
+            }
+            expr => get_value_type(expr),
+        };
+    };
+
+    let arg_type = || {
+        function
+            .args()
+            .first()
+            .map(|arg| get_view_field_type(arg, source))
+            .unwrap_or(FieldType::Any)
+    };
+
+    match function.name().unwrap_or_default() {
+        "count" => FieldType::Number,
+        name if name.starts_with("math::") => FieldType::Number,
+        "time::min" | "time::max" => FieldType::Date,
+        "array::first" | "array::last" => match arg_type() {
+            FieldType::Array { item } => *item,
+            _ => FieldType::Any,
+        },
+        "array::distinct" | "array::group" => match arg_type() {
+            FieldType::Array { item } => FieldType::Array { item },
+            item => FieldType::Array { item: item.into() },
+        },
+        _ => FieldType::Any,
+    }
+}
+
+fn get_index_metas(indexes: &[DefineIndexStatement]) -> IndexMetas {
+    indexes
+        .iter()
+        .filter_map(|index| {
+            let kind = match &index.index {
+                sql::Index::Idx => IndexKind::Normal,
+                sql::Index::Uniq => IndexKind::Unique,
+                sql::Index::Search(params) => IndexKind::Search {
+                    analyzer: params.az.to_string(),
+                    highlights: params.hl,
+                },
+                sql::Index::MTree(params) => IndexKind::Mtree {
+                    dimension: params.dimension,
+                    distance: params.distance.to_string(),
+                },
+                sql::Index::Hnsw(params) => IndexKind::Hnsw {
+                    dimension: params.dimension,
+                    distance: params.distance.to_string(),
+                },
+                _ => {
+                    eprintln!(
+                        "Skipping index '{}', its type is not yet supported. Please open an issue on github.",
+                        index.name
+                    );
+                    return None;
+                }
+            };
+
+            let index_meta = IndexMeta {
+                columns: index.cols.iter().map(|col| col.to_string()).collect(),
+                kind,
+                comment: index.comment.clone().map(|c| c.0),
+            };
+
+            Some((index.name.to_string(), index_meta))
+        })
+        .collect()
+}
+
+fn get_event_metas(events: &[DefineEventStatement]) -> EventMetas {
+    events
+        .iter()
+        .map(|event| {
+            let event_meta = EventMeta {
+                when: event.when.to_string(),
+                then: event.then.iter().map(|stmt| stmt.to_string()).collect(),
+                comment: event.comment.clone().map(|c| c.0),
+            };
+
+            (event.name.to_string(), event_meta)
+        })
+        .collect()
+}
+
+fn get_field_metas(fields: &[DefineFieldStatement], prefix: String) -> FieldMetas {
+    let mut field_metas = BTreeMap::new();
+
+    let mut fields = fields.iter();
+    while let Some(field) = &fields.next() {
+        let path = field.name.to_string();
+        let name = path[prefix.len()..].to_string();
+
+        let permissions = &field.permissions;
+        let permissions = (!permissions.is_full()).then(|| FieldPermissions {
+            select: permissions.select.to_string(),
+            create: permissions.create.to_string(),
+            update: permissions.update.to_string(),
+        });
+
+        let field_meta = FieldMeta {
+            r#type: get_field_type(path, field.kind.clone(), &mut fields),
+            has_default: field.default.is_some(),
+            default: field.default.as_ref().map(|v| v.to_string()),
+            default_value: field.default.as_ref().and_then(get_constant_value),
+            comment: field.comment.clone().map(|c| c.to_string()),
+            readonly: field.readonly,
+            flexible: field.flex,
+            value: field.value.as_ref().map(|v| v.to_string()),
+            computed: field.value.as_ref().is_some_and(is_computed_value),
+            assert: field.assert.as_ref().map(|v| v.to_string()),
+            permissions,
+            on_delete: field.reference.as_ref().map(|r| r.on_delete.to_string()),
+        };
+
+        field_metas.insert(name, field_meta);
+    }
+
+    field_metas
+}
+
+fn get_field_type<'a>(
+    path: String,
+    kind: Option<Kind>,
+    fields: &mut (impl Iterator<Item = &'a DefineFieldStatement> + std::clone::Clone),
+) -> FieldType {
+    match kind {
+        None => FieldType::Any,
+        Some(kind) => match kind {
+            Kind::Any => FieldType::Any,
+            Kind::Null => FieldType::Null,
+            Kind::Uuid => FieldType::Uuid,
+            Kind::Bytes => FieldType::Bytes,
+            Kind::Bool => FieldType::Boolean,
+            Kind::String => FieldType::String,
+            Kind::Datetime => FieldType::Date,
+            Kind::Decimal => FieldType::Decimal,
+            Kind::Duration => FieldType::Duration,
+            Kind::Float | Kind::Int | Kind::Number => FieldType::Number,
+            Kind::Option(kind) => {
+                let inner = get_field_type(path, Some(*kind), fields);
+                FieldType::Option {
+                    inner: inner.into(),
+                }
+            }
+            Kind::Object => {
+                let prefix = format!("{path}.");
+
+                let subfields: Vec<_> = fields
+                    .take_while_ref(|f| f.name.to_string().starts_with(&prefix))
+                    .cloned()
+                    .collect();
+
+                if subfields.is_empty() {
+                    FieldType::Object { fields: None }
+                } else {
+                    let subfields = get_field_metas(&subfields, prefix);
+                    FieldType::Object {
+                        fields: Some(subfields),
+                    }
+                }
+            }
+            Kind::Record(tables) => {
+                let tables = tables.iter().map(|t| t.to_string()).collect();
+                FieldType::Record { tables }
+            }
+            Kind::References(table, _) => {
+                let tables = table.iter().map(|t| t.to_string()).collect();
+                FieldType::References { tables }
+            }
+            Kind::Either(kinds) => {
+                let variants: Vec<_> = kinds
+                    .into_iter()
+                    .map(|kind| get_field_type(path.clone(), Some(kind), fields))
+                    .collect();
+
+                FieldType::Union(get_union_variant(variants))
+            }
+            Kind::Set(inner, _) | Kind::Array(inner, _) => {
+                // Only the `field[*]` definition describes the items, untyped arrays have none
+                let item_path = format!("{path}[*]");
+                let item_definition = fields
+                    .clone()
+                    .next()
+                    .filter(|f| f.name.to_string() == item_path);
+
+                let item = match item_definition {
+                    Some(item_definition) => {
+                        fields.next();
+                        get_field_type(item_path, item_definition.kind.clone(), fields)
+                    }
+                    None => get_field_type(path, Some(*inner), fields),
+                };
+
+                FieldType::Array { item: item.into() }
+            }
+            Kind::Literal(literal) => match literal {
+                sql::Literal::String(value) => Literal::String {
+                    value: value[..].to_string(),
+                }
+                .into(),
+                sql::Literal::Number(number) => Literal::Number {
+                    value: number.as_float(),
+                }
+                .into(),
+                sql::Literal::Array(kinds) => {
+                    let items: Vec<_> = kinds
+                        .into_iter()
+                        .map(|kind| get_field_type(path.clone(), Some(kind), fields))
+                        .collect();
+
+                    Literal::Array { items }.into()
+                }
+                sql::Literal::Object(map) => {
+                    let fields = map
+                        .into_iter()
+                        .map(|(name, kind)| {
+                            let field_type = get_field_type(
+                                format!("{path}.{name}"),
+                                Some(kind),
+                                &mut iter::empty(),
+                            );
+                            (name, FieldMeta::new(field_type))
+                        })
+                        .collect();
+
+                    FieldType::Object {
+                        fields: Some(fields),
+                    }
+                }
+                _ => unimplemented!(
+                    "The type of field '{path}' is not yet supported. Please open an issue on github."
+                ),
+            },
+            _ => unimplemented!(
+                "The type of field '{path}' is not yet supported. Please open an issue on github."
+            ),
+        },
+    }
+}
+
+/// Checks whether the VALUE clause of a field ignores the value sent by the client
+fn is_computed_value(value: &sql::Value) -> bool {
+    let mut params = BTreeSet::new();
+    collect_params(value, &mut params);
+
+    !params.contains("value") && !params.contains("input")
+}
+
+/// Collects the names of the params referenced anywhere in an expression or a statement
+fn collect_params(value: &sql::Value, params: &mut BTreeSet<String>) {
+    match value {
+        sql::Value::Param(param) => {
+            params.insert(param.0.0.clone());
+        }
+        sql::Value::Array(array) => collect_values_params(array.iter(), params),
+        sql::Value::Object(object) => collect_values_params(object.values(), params),
+        sql::Value::Idiom(idiom) => {
+            for part in idiom.iter() {
+                match part {
+                    sql::Part::Start(value) | sql::Part::Where(value) | sql::Part::Value(value) => {
+                        collect_params(value, params)
+                    }
+                    sql::Part::Method(_, args) => collect_values_params(args.iter(), params),
+                    _ => {}
+                }
+            }
+        }
+        sql::Value::Cast(cast) => collect_params(&cast.1, params),
+        sql::Value::Block(block) => collect_block_params(block, params),
+        sql::Value::Future(future) => collect_block_params(&future.0, params),
+        sql::Value::Function(function) => collect_values_params(function.args().iter(), params),
+        sql::Value::Model(model) => collect_values_params(model.args.iter(), params),
+        sql::Value::Closure(closure) => collect_params(&closure.body, params),
+        sql::Value::Expression(expression) => match &**expression {
+            sql::Expression::Unary { v, .. } => collect_params(v, params),
+            sql::Expression::Binary { l, r, .. } => {
+                collect_params(l, params);
+                collect_params(r, params);
+            }
+            _ => {}
+        },
+        sql::Value::Subquery(subquery) => match &**subquery {
+            sql::Subquery::Value(value) => collect_params(value, params),
+            sql::Subquery::Ifelse(stmt) => collect_ifelse_params(stmt, params),
+            sql::Subquery::Output(stmt) => collect_params(&stmt.what, params),
+            sql::Subquery::Select(stmt) => collect_select_params(stmt, params),
+            sql::Subquery::Create(stmt) => {
+                collect_write_params(&stmt.what, &stmt.data, &None, params)
+            }
+            sql::Subquery::Update(stmt) => {
+                collect_write_params(&stmt.what, &stmt.data, &stmt.cond, params)
+            }
+            sql::Subquery::Upsert(stmt) => {
+                collect_write_params(&stmt.what, &stmt.data, &stmt.cond, params)
+            }
+            sql::Subquery::Delete(stmt) => {
+                collect_write_params(&stmt.what, &None, &stmt.cond, params)
+            }
+            sql::Subquery::Relate(stmt) => collect_relate_params(stmt, params),
+            sql::Subquery::Insert(stmt) => collect_insert_params(stmt, params),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+fn collect_values_params<'a>(
+    values: impl Iterator<Item = &'a sql::Value>,
+    params: &mut BTreeSet<String>,
+) {
+    for value in values {
+        collect_params(value, params);
+    }
+}
+
+fn collect_block_params(block: &sql::Block, params: &mut BTreeSet<String>) {
+    for entry in block.iter() {
+        match entry {
+            sql::Entry::Value(value) => collect_params(value, params),
+            sql::Entry::Set(stmt) => collect_params(&stmt.what, params),
+            sql::Entry::Ifelse(stmt) => collect_ifelse_params(stmt, params),
+            sql::Entry::Output(stmt) => collect_params(&stmt.what, params),
+            sql::Entry::Select(stmt) => collect_select_params(stmt, params),
+            sql::Entry::Create(stmt) => collect_write_params(&stmt.what, &stmt.data, &None, params),
+            sql::Entry::Update(stmt) => {
+                collect_write_params(&stmt.what, &stmt.data, &stmt.cond, params)
+            }
+            sql::Entry::Upsert(stmt) => {
+                collect_write_params(&stmt.what, &stmt.data, &stmt.cond, params)
+            }
+            sql::Entry::Delete(stmt) => collect_write_params(&stmt.what, &None, &stmt.cond, params),
+            sql::Entry::Relate(stmt) => collect_relate_params(stmt, params),
+            sql::Entry::Insert(stmt) => collect_insert_params(stmt, params),
+            sql::Entry::Foreach(stmt) => {
+                collect_params(&stmt.range, params);
+                collect_block_params(&stmt.block, params);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_ifelse_params(stmt: &IfelseStatement, params: &mut BTreeSet<String>) {
+    for (condition, then) in &stmt.exprs {
+        collect_params(condition, params);
+        collect_params(then, params);
+    }
+
+    if let Some(close) = &stmt.close {
+        collect_params(close, params);
+    }
+}
+
+fn collect_select_params(stmt: &SelectStatement, params: &mut BTreeSet<String>) {
+    for field in stmt.expr.0.iter() {
+        if let sql::Field::Single { expr, .. } = field {
+            collect_params(expr, params);
+        }
+    }
+
+    collect_write_params(&stmt.what, &None, &stmt.cond, params);
+}
+
+fn collect_write_params(
+    what: &sql::Values,
+    data: &Option<sql::Data>,
+    cond: &Option<sql::Cond>,
+    params: &mut BTreeSet<String>,
+) {
+    collect_values_params(what.iter(), params);
+
+    if let Some(data) = data {
+        collect_data_params(data, params);
+    }
+
+    if let Some(cond) = cond {
+        collect_params(&cond.0, params);
+    }
+}
+
+fn collect_relate_params(stmt: &RelateStatement, params: &mut BTreeSet<String>) {
+    collect_values_params([&stmt.from, &stmt.kind, &stmt.with].into_iter(), params);
+
+    if let Some(data) = &stmt.data {
+        collect_data_params(data, params);
+    }
+}
+
+fn collect_insert_params(stmt: &InsertStatement, params: &mut BTreeSet<String>) {
+    collect_values_params(stmt.into.iter(), params);
+    collect_data_params(&stmt.data, params);
+
+    if let Some(update) = &stmt.update {
+        collect_data_params(update, params);
+    }
+}
+
+fn collect_data_params(data: &sql::Data, params: &mut BTreeSet<String>) {
+    match data {
+        sql::Data::SetExpression(items) | sql::Data::UpdateExpression(items) => {
+            collect_values_params(items.iter().map(|(_, _, value)| value), params)
+        }
+        sql::Data::PatchExpression(value)
+        | sql::Data::MergeExpression(value)
+        | sql::Data::ReplaceExpression(value)
+        | sql::Data::ContentExpression(value)
+        | sql::Data::SingleExpression(value) => collect_params(value, params),
+        sql::Data::ValuesExpression(rows) => {
+            collect_values_params(rows.iter().flatten().map(|(_, value)| value), params)
+        }
+        _ => {}
+    }
+}
+
+/// Infers the type of a value, values that need to be computed can be anything
+fn get_value_type(value: &sql::Value) -> FieldType {
+    match value {
+        sql::Value::None => FieldType::Option {
+            inner: FieldType::Any.into(),
+        },
+        sql::Value::Null => FieldType::Null,
+        sql::Value::Bool(_) => FieldType::Boolean,
+        sql::Value::Number(sql::Number::Decimal(_)) => FieldType::Decimal,
+        sql::Value::Number(_) => FieldType::Number,
+        sql::Value::Strand(_) => FieldType::String,
+        sql::Value::Duration(_) => FieldType::Duration,
+        sql::Value::Datetime(_) => FieldType::Date,
+        sql::Value::Uuid(_) => FieldType::Uuid,
+        sql::Value::Bytes(_) => FieldType::Bytes,
+        sql::Value::Thing(thing) => FieldType::Record {
+            tables: vec![thing.tb.clone()],
+        },
+        sql::Value::Array(array) => {
+            let mut variants: Vec<FieldType> = vec![];
+
+            for item in array.iter().map(get_value_type) {
+                let json = serde_json::to_value(&item).ok();
+                if !variants
+                    .iter()
+                    .any(|variant| serde_json::to_value(variant).ok() == json)
+                {
+                    variants.push(item);
+                }
+            }
+
+            let item = match variants.len() {
+                0 => FieldType::Any,
+                1 => variants.remove(0),
+                _ => FieldType::Union(Union::Normal { variants }),
+            };
+
+            FieldType::Array { item: item.into() }
+        }
+        sql::Value::Object(object) => {
+            let fields = object
+                .iter()
+                .map(|(name, value)| (name.clone(), FieldMeta::new(get_value_type(value))))
+                .collect();
+
+            FieldType::Object {
+                fields: Some(fields),
+            }
+        }
+        _ => FieldType::Any,
+    }
+}
+
+/// Converts the value into JSON, if it is a constant literal, that needs no computation
+fn get_constant_value(value: &sql::Value) -> Option<serde_json::Value> {
+    match value {
+        sql::Value::Null => Some(serde_json::Value::Null),
+        sql::Value::Bool(bool) => Some((*bool).into()),
+        sql::Value::Strand(string) => Some(string[..].into()),
+        sql::Value::Number(sql::Number::Int(int)) => Some((*int).into()),
+        sql::Value::Number(number) => {
+            serde_json::Number::from_f64(number.as_float()).map(Into::into)
+        }
+        sql::Value::Array(array) => array
+            .iter()
+            .map(get_constant_value)
+            .collect::<Option<_>>()
+            .map(serde_json::Value::Array),
+        sql::Value::Object(object) => object
+            .iter()
+            .map(|(key, value)| Some((key.clone(), get_constant_value(value)?)))
+            .collect::<Option<_>>()
+            .map(serde_json::Value::Object),
+        _ => None,
+    }
+}
+
+fn get_union_variant(variants: Vec<FieldType>) -> Union {
+    let strings: Vec<_> = variants
+        .iter()
+        .filter_map(|v| match v {
+            FieldType::Literal(Literal::String { value }) => Some(value.clone()),
+            _ => None,
+        })
+        .collect();
+
+    if strings.len() == variants.len() {
+        return Union::Enum(Enum::String { variants: strings });
+    }
+
+    let numbers: Vec<_> = variants
+        .iter()
+        .filter_map(|v| match v {
+            FieldType::Literal(Literal::Number { value }) => Some(*value),
+            _ => None,
+        })
+        .collect();
+
+    if numbers.len() == variants.len() {
+        return Union::Enum(Enum::Number { variants: numbers });
+    }
+
+    Union::Normal { variants }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_value_clause(value: &str) -> sql::Value {
+        let sql = format!("DEFINE FIELD computed ON test VALUE {value}");
+
+        match parse_sql(&sql)
+            .expect("The SurrealQL is valid.")
+            .into_iter()
+            .next()
+        {
+            Some(sql::Statement::Define(sql::statements::DefineStatement::Field(field))) => {
+                field.value.expect("The field has a VALUE clause.")
+            }
+            _ => panic!("The statement is a field definition."),
+        }
+    }
+
+    #[test]
+    fn access_variables_are_collected_from_the_queries() {
+        let sql = "DEFINE ACCESS account ON DATABASE TYPE RECORD
+            SIGNUP (CREATE account SET email = $email, note = 'costs $5 or $price')
+            SIGNIN (SELECT * FROM account WHERE email = $email AND crypto::argon2::compare(pass, $pass) AND $auth = NONE)";
+
+        let access = match parse_sql(sql)
+            .expect("The SurrealQL is valid.")
+            .into_iter()
+            .next()
+        {
+            Some(sql::Statement::Define(sql::statements::DefineStatement::Access(access))) => {
+                access
+            }
+            _ => panic!("The statement is an access definition."),
+        };
+
+        let accesses = get_access_metas(vec![access], &TableMetas::new(), &ParamMetas::new());
+        let account = &accesses["account"];
+
+        let signup = account
+            .signup
+            .as_ref()
+            .expect("The access has a SIGNUP query.");
+        assert_eq!(signup.keys().collect::<Vec<_>>(), ["email"]);
+
+        let signin = account
+            .signin
+            .as_ref()
+            .expect("The access has a SIGNIN query.");
+        assert_eq!(signin.keys().collect::<Vec<_>>(), ["email", "pass"]);
+    }
+
+    #[test]
+    fn values_not_referencing_the_input_are_computed() {
+        for value in [
+            "time::now()",
+            "'$value'",
+            "\"$input\"",
+            "$values + 1",
+            "string::concat('$value', $auth.id)",
+        ] {
+            assert!(is_computed_value(&parse_value_clause(value)), "{value}");
+        }
+    }
+
+    #[test]
+    fn values_referencing_the_input_are_not_computed() {
+        for value in [
+            "$value",
+            "$input.name",
+            "string::lowercase($value)",
+            "$value OR time::now()",
+            "[$before, $value][1]",
+            "IF $before THEN $before ELSE $value END",
+            "{ LET $trimmed = string::trim($input); RETURN $trimmed; }",
+            "(SELECT * FROM user WHERE name = $value)",
+        ] {
+            assert!(!is_computed_value(&parse_value_clause(value)), "{value}");
+        }
+    }
+}
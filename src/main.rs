@@ -14,47 +14,110 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see https://www.gnu.org/licenses/.
 
-use core::panic;
 use std::collections::BTreeMap;
-use std::iter;
 
 use clap::CommandFactory;
-use config::Config;
-use itertools::Itertools;
-use serde::{Deserialize, Serialize};
-use surrealdb::sql::statements::DefineFieldStatement;
-use surrealdb::sql::{self, Kind};
-use surrealdb::sql::{statements::DefineStatement, Query, Statement};
-use surrealdb::{engine::any::Any, opt::auth::Root, Surreal};
+use serde::Deserialize;
+use surrealdb::{Surreal, engine::any::Any, opt::auth::Root};
 
-use outputs::{db, ts::TSGenerator};
-use surrealdb::syn::parser::Parser;
-
-mod config;
-mod outputs;
+use surreal_ts::config::{self, Config};
+use surreal_ts::filter::TableFilter;
+use surreal_ts::outputs::{GeneratorRegistry, db, get_selected_outputs, write_files};
+use surreal_ts::sources::{SchemaSource, db::DbSource, ir::IrSource, surql::SurqlSource};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let config = config::get_config()?;
 
+    let reads_from_db = config.from_ir.is_none() && config.from_surql.is_empty();
+
+    let writes_to_db = config.store_meta_in_db && !config.dry_run;
+
+    let db = match reads_from_db || writes_to_db {
+        true => match connect(&config).await? {
+            Some(db) => Some(db),
+            None => return Ok(()),
+        },
+        false => None,
+    };
+
+    let mut schema = match (&config.from_ir, &db) {
+        (Some(path), _) => IrSource::new(path).get_schema().await?,
+        (None, _) if !config.from_surql.is_empty() => {
+            let namespace = config.namespace.as_deref();
+            let database = config.database.as_deref();
+
+            SurqlSource::new(&config.from_surql, namespace, database)
+                .get_schema()
+                .await?
+        }
+        (None, Some(db)) => DbSource::new(db).get_schema().await?,
+        (None, None) => unreachable!("A database connection is always made without a schema file."),
+    };
+
+    schema.tables = TableFilter::new(&config)?.apply(schema.tables);
+
+    let registry = GeneratorRegistry::new(&config)?;
+    for name in get_selected_outputs(&config) {
+        // Plugins are external programs, that could have side effects of their own
+        let plugin = config.plugins.iter().find(|spec| spec.name == name);
+        if let (true, Some(plugin)) = (config.dry_run, plugin) {
+            println!("\nWould run plugin '{name}' ('{}')", plugin.path);
+            continue;
+        }
+
+        let generator = registry.create(&name)?;
+
+        println!("\nGenerating '{}' output...", generator.name());
+        let files = generator.generate(&schema)?;
+
+        match config.dry_run {
+            true => files
+                .iter()
+                .for_each(|file| println!("Would write file '{}'", file.path)),
+            false => write_files(files)?,
+        }
+    }
+
+    match (config.store_meta_in_db, config.dry_run, &db) {
+        (true, true, _) => {
+            println!("\nWould run the following SurrealQL on the database:\n");
+            println!("{}", db::get_store_queries(&schema, &config)?);
+        }
+        (true, false, Some(db)) => db::store_schema_in_db(db, &schema, &config).await?,
+        _ => {}
+    }
+
+    println!("\nAll operations done ✅");
+
+    Ok(())
+}
+
+async fn connect(config: &Config) -> anyhow::Result<Option<Surreal<Any>>> {
     let (Some(namespace), Some(database)) = (&config.namespace, &config.database) else {
-        eprintln!("No 'namespace' or 'database' provided in the config, see the help output for correct usage:\n");
+        eprintln!(
+            "No 'namespace' or 'database' provided in the config, see the help output for correct usage:\n"
+        );
         Config::command().print_help().ok();
-        return Ok(());
+        return Ok(None);
     };
 
-    let mut db = surrealdb::engine::any::connect(&config.address).await?;
-    db.signin(Root {
-        username: &config.username,
-        password: &config.password,
-    })
-    .await?;
+    let db = surrealdb::engine::any::connect(&config.address).await?;
+
+    // An embedded datastore is opened directly, without any users defined
+    if !config.address.starts_with("surrealkv://") {
+        db.signin(Root {
+            username: &config.username,
+            password: &config.password,
+        })
+        .await?;
+    }
 
     let root_info: Option<RootInfo> = db.query("INFO FOR ROOT").await?.take(0)?;
     let root_info = root_info.expect("Failed to get information of the namespaces.");
     if !root_info.namespaces.contains_key(namespace) {
         eprintln!("No namespace '{namespace}' found in the connection!");
-        return Ok(());
+        return Ok(None);
     }
     db.use_ns(namespace).await?;
 
@@ -62,23 +125,11 @@ async fn main() -> anyhow::Result<()> {
     let ns_info = ns_info.expect("Failed to get information of the databases.");
     if !ns_info.databases.contains_key(database) {
         eprintln!("No database '{database}' found in the namespace!");
-        return Ok(());
+        return Ok(None);
     }
     db.use_db(database).await?;
 
-    let table_metas = get_tables_metas_for_db(&mut db).await?;
-
-    if !config.skip_ts_generation {
-        TSGenerator::new(&config).write_tables(&table_metas)?;
-    }
-
-    if config.store_meta_in_db {
-        db::store_tables_in_db(&mut db, table_metas, &config).await?;
-    }
-
-    println!("\nAll operations done ✅");
-
-    Ok(())
+    Ok(Some(db))
 }
 
 #[derive(Deserialize, Debug)]
@@ -90,294 +141,3 @@ struct RootInfo {
 struct NamespaceInfo {
     databases: BTreeMap<String, String>,
 }
-
-#[derive(Deserialize, Debug)]
-struct DatabaseInfo {
-    tables: BTreeMap<String, String>,
-}
-
-#[derive(Deserialize, Debug)]
-struct TableInfo {
-    fields: BTreeMap<String, String>,
-}
-
-async fn get_tables_metas_for_db(db: &mut Surreal<Any>) -> anyhow::Result<TableMetas> {
-    let mut tables = BTreeMap::new();
-
-    let info: Option<DatabaseInfo> = db.query("INFO FOR DB").await?.take(0)?;
-    let info = info.expect("Failed to get information of the database.");
-
-    let every_table = info.tables.into_values().join(";\n");
-    let result = parse_sql(&every_table);
-
-    for stmt in result {
-        let Statement::Define(DefineStatement::Table(table)) = stmt else {
-            panic!("Database table list contained define statement for not table.")
-        };
-
-        println!("Processing table: {}", table.name);
-
-        let fields = get_field_metas_for_table(db, &table.name).await?;
-        let table_meta = TableMeta {
-            fields: get_field_metas(&fields, "".to_string()),
-            comment: table.comment.map(|c| c.to_string()),
-        };
-
-        tables.insert(table.name.to_string(), table_meta);
-    }
-
-    Ok(tables)
-}
-
-async fn get_field_metas_for_table(
-    db: &mut Surreal<Any>,
-    table: &str,
-) -> anyhow::Result<Vec<DefineFieldStatement>> {
-    let mut fields = vec![];
-
-    let info: Option<TableInfo> = db.query(format!("INFO FOR TABLE {table}")).await?.take(0)?;
-    let info = info.expect(&format!("Failed to get information of table {table}."));
-
-    let every_field = info.fields.into_values().join(";\n");
-    let result = parse_sql(&every_field);
-
-    for stmt in result {
-        let Statement::Define(DefineStatement::Field(field)) = stmt else {
-            panic!("The field list of table '{table}' contained define statement for not field.")
-        };
-
-        fields.push(field);
-    }
-
-    Ok(fields)
-}
-
-fn parse_sql(sql: &str) -> Query {
-    let mut parser = Parser::new(sql.as_bytes());
-    let mut stack = reblessive::Stack::new();
-    let result = stack.enter(|ctx| parser.parse_query(ctx)).finish().unwrap();
-
-    result
-}
-
-type TableMetas = BTreeMap<String, TableMeta>;
-type FieldMetas = BTreeMap<String, FieldMeta>;
-
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct TableMeta {
-    fields: FieldMetas,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    comment: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct FieldMeta {
-    r#type: FieldType,
-    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
-    has_default: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    comment: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-#[serde(tag = "name")]
-enum FieldType {
-    Any,
-    Null,
-    Boolean,
-    String,
-    Number,
-    Decimal,
-    Duration,
-    Uuid,
-    Date,
-    Bytes,
-    Option { inner: Box<FieldType> },
-    Record { tables: Vec<String> },
-    Array { item: Box<FieldType> },
-    Object { fields: Option<FieldMetas> },
-    Union(Union),
-    Literal(Literal),
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-#[serde(untagged)]
-enum Union {
-    Normal { variants: Vec<FieldType> },
-    Enum(Enum),
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-#[serde(tag = "enum")]
-enum Enum {
-    String { variants: Vec<String> },
-    Number { variants: Vec<f64> },
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-#[serde(tag = "kind")]
-enum Literal {
-    String { value: String },
-    Number { value: f64 },
-    Array { items: Vec<FieldType> },
-}
-
-impl Into<FieldType> for Literal {
-    fn into(self) -> FieldType {
-        FieldType::Literal(self)
-    }
-}
-
-fn get_field_metas(fields: &Vec<DefineFieldStatement>, prefix: String) -> FieldMetas {
-    let mut field_metas = BTreeMap::new();
-
-    let mut fields = fields.into_iter();
-    while let Some(field) = &fields.next() {
-        let path = field.name.to_string();
-        let name = path[prefix.len()..].to_string();
-
-        let field_meta = FieldMeta {
-            r#type: get_field_type(path, field.kind.clone(), &mut fields),
-            has_default: field.default.is_some(),
-            comment: field.comment.clone().map(|c| c.to_string()),
-        };
-
-        field_metas.insert(name, field_meta);
-    }
-
-    field_metas
-}
-
-fn get_field_type<'a>(
-    path: String,
-    kind: Option<Kind>,
-    fields: &mut (impl Iterator<Item = &'a DefineFieldStatement> + std::clone::Clone),
-) -> FieldType {
-    match kind {
-        None => FieldType::Any,
-        Some(kind) => match kind {
-            Kind::Any => FieldType::Any,
-            Kind::Null => FieldType::Null,
-            Kind::Uuid => FieldType::Uuid,
-            Kind::Bytes => FieldType::Bytes,
-            Kind::Bool => FieldType::Boolean,
-            Kind::String => FieldType::String,
-            Kind::Datetime => FieldType::Date,
-            Kind::Decimal => FieldType::Decimal,
-            Kind::Duration => FieldType::Duration,
-            Kind::Float | Kind::Int | Kind::Number => FieldType::Number,
-            Kind::Option(kind) => {
-                let inner = get_field_type(path, Some(*kind), fields);
-                FieldType::Option { inner: inner.into() }
-            },
-            Kind::Object => {
-                let prefix = format!("{path}.");
-
-                let subfields: Vec<_> = fields
-                    .take_while_ref(|f| f.name.to_string().starts_with(&prefix))
-                    .cloned()
-                    .collect();
-
-                if subfields.len() == 0 {
-                    FieldType::Object{ fields: None }
-                } else {
-                    let subfields = get_field_metas(&subfields, prefix);
-                    FieldType::Object{ fields: Some(subfields) }
-                }
-            }
-            Kind::Record(tables) => {
-                let tables = tables.iter().map(|t| t.to_string()).collect();
-                FieldType::Record{ tables }
-            }
-            Kind::Either(kinds) => {
-                let variants: Vec<_> = kinds
-                    .into_iter()
-                    .map(|kind| get_field_type(path.clone(), Some(kind), fields))
-                    .collect();
-
-                FieldType::Union(get_union_variant(variants))
-            }
-            Kind::Set(inner, _) | Kind::Array(inner, _) => {
-                let item = match fields.next() {
-                    Some(item_definition) => {
-                        get_field_type(
-                            item_definition.name.to_string(),
-                            item_definition.kind.clone(),
-                            fields,
-                        )
-                    },
-                    None => get_field_type(path, Some(*inner), fields)
-                };
-
-                FieldType::Array{ item: item.into() }
-            }
-            Kind::Literal(literal) => match literal {
-                sql::Literal::String(value) => Literal::String{value: value[..].to_string()}.into(),
-                sql::Literal::Number(number) => Literal::Number{value: number.as_float()}.into(),
-                sql::Literal::Array(kinds) => {
-                    let items: Vec<_> = kinds
-                        .into_iter()
-                        .map(|kind| get_field_type(path.clone(), Some(kind), fields))
-                        .collect();
-
-                    Literal::Array{ items }.into()
-                },
-                sql::Literal::Object(map) => {
-                    let fields = map.into_iter().map(|(name, kind)| {
-                        let field_type = get_field_type(format!("{path}.{name}"), Some(kind), &mut iter::empty());
-                        let field_meta = FieldMeta {
-                            r#type: field_type,
-                            has_default: false,
-                            comment: None,
-                        };
-
-                        (name, field_meta)
-                    })
-                    .collect();
-
-                    FieldType::Object{ fields: Some(fields) }
-                },
-                _ => unimplemented!(
-                    "The type of field '{path}' is not yet supported. Please open an issue on github."
-                ),
-            },
-            _ => unimplemented!(
-                "The type of field '{path}' is not yet supported. Please open an issue on github."
-            ),
-        },
-    }
-}
-
-fn get_union_variant(variants: Vec<FieldType>) -> Union {
-    let strings: Vec<_> = variants
-        .iter()
-        .filter_map(|v| match v {
-            FieldType::Literal(Literal::String { value }) => Some(value.clone()),
-            _ => None,
-        })
-        .collect();
-
-    if strings.len() == variants.len() {
-        return Union::Enum(Enum::String { variants: strings });
-    }
-
-    let numbers: Vec<_> = variants
-        .iter()
-        .filter_map(|v| match v {
-            FieldType::Literal(Literal::Number { value }) => Some(value.clone()),
-            _ => None,
-        })
-        .collect();
-
-    if numbers.len() == variants.len() {
-        return Union::Enum(Enum::Number { variants: numbers });
-    }
-
-    Union::Normal { variants }
-}
@@ -1,28 +1,477 @@
-use surrealdb::{Surreal, engine::any::Any};
+use std::collections::BTreeMap;
+use std::iter;
 
-use crate::{TableMeta, TableMetas, config::Config};
+use serde::Serialize;
+use surrealdb::{Connection, Surreal};
 
-pub async fn store_tables_in_db(
-    db: &mut Surreal<Any>,
-    tables: TableMetas,
+use crate::filter::{get_subfields, matches_glob};
+use crate::{
+    AccessMetas, FieldMetas, FunctionMetas, ParamMetas, Schema, TableMetas, config::Config,
+};
+
+/// The kinds of definitions, that are stored in metadata tables of their own
+const DEFINITION_KINDS: [&str; 3] = ["functions", "params", "accesses"];
+
+/// Writes the metas of the schema into the metadata tables in a single transaction.
+/// The records are upserted and only the ones of removed definitions are deleted,
+/// so readers never see an empty or half-populated table.
+pub async fn store_schema_in_db<C: Connection>(
+    db: &Surreal<C>,
+    schema: &Schema,
     config: &Config,
 ) -> anyhow::Result<()> {
-    println!("Writing table metadata into database...");
+    println!("Writing schema metadata into database...");
+
+    let queries = get_store_queries(schema, config)?;
+    db.query(queries).await?.check()?;
+
+    Ok(())
+}
+
+/// Creates the SurrealQL statements that store the metas of the schema into the database
+pub fn get_store_queries(schema: &Schema, config: &Config) -> anyhow::Result<String> {
+    let tables = &get_stored_tables(&schema.tables, config);
     let metadata_table_name = &config.metadata_table_name;
+    let permission = &config.metadata_select_permission;
+    let mut queries = vec![];
+
+    if let Some(location) = get_use_statement(config) {
+        queries.push(location);
+    }
+
+    queries.push("BEGIN TRANSACTION;".to_string());
+
+    if config.metadata_schemafull {
+        queries.push(format!(
+            "DEFINE TABLE OVERWRITE {metadata_table_name} SCHEMAFULL
+            PERMISSIONS
+                FOR select {permission};"
+        ));
 
-    db.query(format!(
-        "REMOVE TABLE {metadata_table_name};
-        DEFINE TABLE {metadata_table_name} SCHEMALESS
+        for (field, kind) in get_metadata_fields() {
+            queries.push(format!(
+                "DEFINE FIELD OVERWRITE {field} ON {metadata_table_name} {kind};"
+            ));
+        }
+    } else {
+        queries.push(format!(
+            "DEFINE TABLE OVERWRITE {metadata_table_name} SCHEMALESS
             PERMISSIONS
-                FOR select FULL;"
+                FOR select {permission};"
+        ));
+
+        // The fields are left over, if the table was stored SCHEMAFULL before
+        for (field, _) in get_metadata_fields() {
+            queries.push(format!(
+                "REMOVE FIELD IF EXISTS {field} ON {metadata_table_name};"
+            ));
+        }
+    }
+
+    queries.extend(get_upsert_queries(metadata_table_name, tables)?);
+
+    let functions = get_stored_functions(&schema.functions, config);
+    queries.extend(get_definition_queries("functions", &functions, config)?);
+
+    let params = get_stored_params(&schema.params, config);
+    queries.extend(get_definition_queries("params", &params, config)?);
+
+    let accesses = get_stored_accesses(&schema.accesses, config);
+    queries.extend(get_definition_queries("accesses", &accesses, config)?);
+
+    if let Some(history_table_name) = &config.metadata_history_table_name {
+        queries.push(get_history_query(history_table_name, permission, tables)?);
+    }
+
+    queries.push("COMMIT TRANSACTION;".to_string());
+
+    Ok(queries.join("\n"))
+}
+
+/// Creates the statements that upsert a record for every meta, and delete the ones of the
+/// metas that are not present anymore
+fn get_upsert_queries<T: Serialize>(
+    table_name: &str,
+    metas: &BTreeMap<String, T>,
+) -> anyhow::Result<Vec<String>> {
+    let mut queries = vec![];
+
+    let table = serde_json::to_string(table_name)?;
+    for (name, meta) in metas {
+        let id = serde_json::to_string(name)?;
+        let content = serde_json::to_string(meta)?;
+
+        queries.push(format!(
+            "UPSERT type::thing({table}, {id}) CONTENT {content};"
+        ));
+    }
+
+    let names = serde_json::to_string(&metas.keys().collect::<Vec<_>>())?;
+    queries.push(format!(
+        "DELETE {table_name} WHERE meta::id(id) NOT IN {names};"
+    ));
+
+    Ok(queries)
+}
+
+/// Creates the statements that store the definitions of a kind into their own metadata
+/// table, which is removed when the database has no such definitions
+fn get_definition_queries<T: Serialize>(
+    kind: &str,
+    metas: &BTreeMap<String, T>,
+    config: &Config,
+) -> anyhow::Result<Vec<String>> {
+    let table_name = get_definition_table_name(config, kind);
+
+    if metas.is_empty() {
+        return Ok(vec![format!("REMOVE TABLE IF EXISTS {table_name};")]);
+    }
+
+    let permission = &config.metadata_select_permission;
+    let mut queries = vec![format!(
+        "DEFINE TABLE OVERWRITE {table_name} SCHEMALESS
+            PERMISSIONS
+                FOR select {permission};"
+    )];
+    queries.extend(get_upsert_queries(&table_name, metas)?);
+
+    Ok(queries)
+}
+
+/// The name of the metadata table of a kind of definitions, like `table_meta_functions`
+fn get_definition_table_name(config: &Config, kind: &str) -> String {
+    format!("{}_{kind}", config.metadata_table_name)
+}
+
+/// The names of every table that surreal-ts stores metadata in
+pub fn get_metadata_table_names(config: &Config) -> Vec<String> {
+    iter::once(config.metadata_table_name.clone())
+        .chain(DEFINITION_KINDS.map(|kind| get_definition_table_name(config, kind)))
+        .chain(config.metadata_history_table_name.clone())
+        .collect()
+}
+
+/// Creates the statements that add a snapshot of the table metas to the history table,
+/// unless the latest snapshot there has the same content hash
+fn get_history_query(
+    history_table_name: &str,
+    permission: &str,
+    tables: &TableMetas,
+) -> anyhow::Result<String> {
+    let snapshot = serde_json::to_string(tables)?;
+    let version = serde_json::to_string(env!("CARGO_PKG_VERSION"))?;
+
+    Ok(format!(
+        "DEFINE TABLE OVERWRITE {history_table_name} SCHEMALESS
+            PERMISSIONS
+                FOR select {permission};
+LET $snapshot = {snapshot};
+LET $hash = crypto::sha256(<string> $snapshot);
+LET $previous = SELECT * FROM ONLY {history_table_name} ORDER BY createdAt DESC LIMIT 1;
+IF $previous.hash != $hash {{
+    CREATE {history_table_name} CONTENT {{
+        hash: $hash,
+        createdAt: time::now(),
+        version: {version},
+        tables: $snapshot,
+        diff: value::diff($previous.tables ?? {{}}, $snapshot),
+    }};
+}};"
     ))
-    .await?;
+}
 
-    for (name, table_meta) in tables {
-        db.create::<Option<TableMeta>>((metadata_table_name, name.clone()))
-            .content(table_meta)
-            .await?;
+/// Switches to the namespace and database of the metadata, if they differ from the schema's
+fn get_use_statement(config: &Config) -> Option<String> {
+    let namespace = config
+        .metadata_namespace
+        .as_ref()
+        .map(|ns| format!(" NS {ns}"));
+    let database = config
+        .metadata_database
+        .as_ref()
+        .map(|db| format!(" DB {db}"));
+
+    match (namespace, database) {
+        (None, None) => None,
+        (namespace, database) => Some(format!(
+            "USE{}{};",
+            namespace.unwrap_or_default(),
+            database.unwrap_or_default()
+        )),
     }
+}
 
-    Ok(())
+/// Removes the tables and comments from the metas, that should not be stored in the database
+fn get_stored_tables(tables: &TableMetas, config: &Config) -> TableMetas {
+    let is_stored = |table: &str| {
+        !config
+            .metadata_exclude
+            .iter()
+            .any(|pattern| matches_glob(pattern, table))
+    };
+
+    tables
+        .iter()
+        .filter(|(name, _)| is_stored(name))
+        .map(|(name, meta)| {
+            let mut meta = meta.clone();
+
+            meta.referenced_by
+                .retain(|reference| is_stored(&reference.table));
+
+            if config.metadata_strip_comments {
+                meta.comment = None;
+                strip_comments(&mut meta.fields);
+
+                if let Some(record_id) = &mut meta.record_id {
+                    record_id.comment = None;
+                }
+
+                for index in meta.indexes.values_mut() {
+                    index.comment = None;
+                }
+
+                for event in meta.events.values_mut() {
+                    event.comment = None;
+                }
+            }
+
+            (name.clone(), meta)
+        })
+        .collect()
+}
+
+fn get_stored_functions(functions: &FunctionMetas, config: &Config) -> FunctionMetas {
+    let mut functions = functions.clone();
+
+    if config.metadata_strip_comments {
+        for function in functions.values_mut() {
+            function.comment = None;
+        }
+    }
+
+    functions
+}
+
+fn get_stored_params(params: &ParamMetas, config: &Config) -> ParamMetas {
+    let mut params = params.clone();
+
+    if config.metadata_strip_comments {
+        for param in params.values_mut() {
+            param.comment = None;
+        }
+    }
+
+    params
+}
+
+fn get_stored_accesses(accesses: &AccessMetas, config: &Config) -> AccessMetas {
+    let mut accesses = accesses.clone();
+
+    if config.metadata_strip_comments {
+        for access in accesses.values_mut() {
+            access.comment = None;
+            access.signup.iter_mut().for_each(strip_comments);
+            access.signin.iter_mut().for_each(strip_comments);
+        }
+    }
+
+    accesses
+}
+
+fn strip_comments(fields: &mut FieldMetas) {
+    for meta in fields.values_mut() {
+        meta.comment = None;
+
+        if let Some(subfields) = get_subfields(&mut meta.r#type) {
+            strip_comments(subfields);
+        }
+    }
+}
+
+/// The field definitions of the metadata table, when it is stored SCHEMAFULL
+fn get_metadata_fields() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("fields", "FLEXIBLE TYPE object"),
+        ("comment", "TYPE option<string>"),
+        ("indexes", "FLEXIBLE TYPE option<object>"),
+        ("view", "TYPE option<bool>"),
+        ("schemaless", "TYPE option<bool>"),
+        ("recordId", "FLEXIBLE TYPE option<object>"),
+        ("referencedBy", "FLEXIBLE TYPE option<array<object>>"),
+        ("events", "FLEXIBLE TYPE option<object>"),
+        ("changefeed", "FLEXIBLE TYPE option<object>"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use surrealdb::engine::local::Mem;
+
+    use super::*;
+    use crate::{FunctionMeta, TableMeta, parse_sql};
+
+    fn get_queries(config: &str, tables: &[&str]) -> String {
+        let schema = Schema {
+            tables: tables
+                .iter()
+                .map(|name| (name.to_string(), TableMeta::default()))
+                .collect(),
+            ..Default::default()
+        };
+
+        get_schema_queries(config, &schema)
+    }
+
+    fn get_schema_queries(config: &str, schema: &Schema) -> String {
+        let config: Config = serde_json::from_str(config).expect("The config is valid.");
+
+        let queries = get_store_queries(schema, &config).expect("The metas are serializable.");
+        parse_sql(&queries).expect("The queries are valid SurrealQL.");
+
+        queries
+    }
+
+    #[test]
+    fn defines_the_fields_of_a_schemafull_metadata_table() {
+        let queries = get_queries(
+            r#"{ "metadata-table-name": "meta", "metadata-schemafull": true }"#,
+            &["user"],
+        );
+
+        assert!(queries.contains("DEFINE TABLE OVERWRITE meta SCHEMAFULL"));
+        assert!(queries.contains("DEFINE FIELD OVERWRITE fields ON meta FLEXIBLE TYPE object;"));
+        assert!(!queries.contains("REMOVE FIELD"));
+    }
+
+    #[test]
+    fn removes_the_fields_of_a_schemaless_metadata_table() {
+        let queries = get_queries(r#"{ "metadata-table-name": "meta" }"#, &["user"]);
+
+        assert!(queries.contains("DEFINE TABLE OVERWRITE meta SCHEMALESS"));
+        assert!(queries.contains("REMOVE FIELD IF EXISTS fields ON meta;"));
+        assert!(!queries.contains("DEFINE FIELD"));
+    }
+
+    #[test]
+    fn upserts_the_tables_and_deletes_the_removed_ones() {
+        let queries = get_queries(r#"{ "metadata-table-name": "meta" }"#, &["post", "user"]);
+
+        assert!(queries.starts_with("BEGIN TRANSACTION;"));
+        assert!(queries.contains(r#"UPSERT type::thing("meta", "post") CONTENT"#));
+        assert!(queries.contains(r#"UPSERT type::thing("meta", "user") CONTENT"#));
+        assert!(queries.contains(r#"DELETE meta WHERE meta::id(id) NOT IN ["post","user"];"#));
+        assert!(queries.ends_with("COMMIT TRANSACTION;"));
+    }
+
+    #[test]
+    fn adds_a_snapshot_to_the_history_table() {
+        let queries = get_queries(
+            r#"{ "metadata-table-name": "meta", "metadata-history-table-name": "history" }"#,
+            &["user"],
+        );
+
+        assert!(queries.contains("DEFINE TABLE OVERWRITE history SCHEMALESS"));
+        assert!(queries.contains("ORDER BY createdAt DESC LIMIT 1;"));
+        assert!(queries.contains("CREATE history CONTENT"));
+        assert!(queries.ends_with("COMMIT TRANSACTION;"));
+    }
+
+    #[test]
+    fn leaves_out_the_history_without_a_history_table() {
+        let queries = get_queries(r#"{ "metadata-table-name": "meta" }"#, &["user"]);
+
+        assert!(!queries.contains("$snapshot"));
+    }
+
+    #[test]
+    fn switches_to_the_location_of_the_metadata() {
+        let queries = get_queries(
+            r#"{ "metadata-table-name": "meta", "metadata-database": "admin" }"#,
+            &["user"],
+        );
+
+        assert!(queries.starts_with("USE DB admin;\nBEGIN TRANSACTION;"));
+
+        let queries = get_queries(
+            r#"{ "metadata-table-name": "meta", "metadata-namespace": "ops", "metadata-database": "admin" }"#,
+            &["user"],
+        );
+
+        assert!(queries.starts_with("USE NS ops DB admin;"));
+    }
+
+    #[test]
+    fn deletes_the_records_of_excluded_tables() {
+        let queries = get_queries(
+            r#"{ "metadata-table-name": "meta", "metadata-exclude": ["audit_*"] }"#,
+            &["audit_log", "user"],
+        );
+
+        assert!(!queries.contains(r#"UPSERT type::thing("meta", "audit_log")"#));
+        assert!(queries.contains(r#"DELETE meta WHERE meta::id(id) NOT IN ["user"];"#));
+    }
+
+    #[test]
+    fn stores_the_functions_in_their_own_table() {
+        let function = FunctionMeta {
+            args: vec![],
+            returns: None,
+            comment: Some("Greets someone".to_string()),
+        };
+        let schema = Schema {
+            functions: [("greet".to_string(), function)].into(),
+            ..Default::default()
+        };
+
+        let queries = get_schema_queries(r#"{ "metadata-table-name": "meta" }"#, &schema);
+
+        assert!(queries.contains("DEFINE TABLE OVERWRITE meta_functions SCHEMALESS"));
+        assert!(queries.contains(r#"UPSERT type::thing("meta_functions", "greet") CONTENT"#));
+        assert!(queries.contains(r#"DELETE meta_functions WHERE meta::id(id) NOT IN ["greet"];"#));
+
+        let queries = get_queries(r#"{ "metadata-table-name": "meta" }"#, &["user"]);
+
+        assert!(queries.contains("REMOVE TABLE IF EXISTS meta_functions;"));
+        assert!(queries.contains("REMOVE TABLE IF EXISTS meta_params;"));
+        assert!(queries.contains("REMOVE TABLE IF EXISTS meta_accesses;"));
+    }
+
+    #[tokio::test]
+    async fn keeps_a_snapshot_only_when_the_metadata_changes() {
+        let db = Surreal::new::<Mem>(()).await.expect("The datastore opens.");
+        db.use_ns("test")
+            .use_db("test")
+            .await
+            .expect("The database is selected.");
+
+        let config: Config = serde_json::from_str(
+            r#"{ "metadata-table-name": "meta", "metadata-history-table-name": "history" }"#,
+        )
+        .expect("The config is valid.");
+        let mut schema = Schema {
+            tables: [("user".to_string(), TableMeta::default())].into(),
+            ..Default::default()
+        };
+
+        for _ in 0..2 {
+            store_schema_in_db(&db, &schema, &config)
+                .await
+                .expect("The metadata is stored.");
+        }
+
+        schema
+            .tables
+            .insert("post".to_string(), TableMeta::default());
+        store_schema_in_db(&db, &schema, &config)
+            .await
+            .expect("The metadata is stored.");
+
+        let mut response = db
+            .query("RETURN count(SELECT * FROM history)")
+            .await
+            .expect("The history can be counted.");
+        let count: Option<usize> = response.take(0).expect("The count is a number.");
+        assert_eq!(count, Some(2));
+    }
 }
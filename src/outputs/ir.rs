@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{OutputFile, OutputGenerator, get_options, options_to_value};
+use crate::{Schema, config::Config, ir::Ir};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct IrOptions {
+    /// Path where the intermediate representation JSON file will be generated
+    pub output: String,
+}
+
+pub struct IrGenerator {
+    options: IrOptions,
+}
+
+impl IrGenerator {
+    pub fn with_options(config: &Config, options: Option<&Value>) -> anyhow::Result<Self> {
+        let defaults = IrOptions {
+            output: config
+                .emit_ir
+                .clone()
+                .unwrap_or_else(|| "db.ir.json".to_string()),
+        };
+
+        Ok(Self {
+            options: get_options("ir", defaults, options)?,
+        })
+    }
+}
+
+impl OutputGenerator for IrGenerator {
+    fn name(&self) -> &str {
+        "ir"
+    }
+
+    fn options(&self) -> Value {
+        options_to_value(&self.options)
+    }
+
+    fn generate(&self, schema: &Schema) -> anyhow::Result<Vec<OutputFile>> {
+        let content = serde_json::to_string_pretty(&Ir::new(schema.clone()))?;
+
+        Ok(vec![OutputFile {
+            path: self.options.output.clone(),
+            content,
+        }])
+    }
+}
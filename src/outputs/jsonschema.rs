@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use super::{
+    Direction, OutputFile, OutputGenerator, create_type_name, create_union, get_options,
+    get_record_id_pattern, has_input, is_flexible, is_generated, options_to_value,
+};
+use crate::{Enum, FieldMetas, FieldType, Literal, Schema, TableMetas, Union, config::Config};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct JsonSchemaOptions {
+    /// Path where the JSON schema file will be generated
+    pub output: String,
+    /// Treat record types as FETCHED version of the linked table
+    pub links_fetched: bool,
+}
+
+pub struct JsonSchemaGenerator {
+    options: JsonSchemaOptions,
+}
+
+impl JsonSchemaGenerator {
+    pub fn with_options(config: &Config, options: Option<&Value>) -> anyhow::Result<Self> {
+        let defaults = JsonSchemaOptions {
+            output: "db.schema.json".to_string(),
+            links_fetched: config.links_fetched,
+        };
+
+        Ok(Self {
+            options: get_options("jsonschema", defaults, options)?,
+        })
+    }
+
+    fn get_object_schema(
+        &self,
+        fields: &FieldMetas,
+        direction: &Direction,
+        table_name: Option<&str>,
+        flexible: bool,
+        all_tables: &TableMetas,
+    ) -> Value {
+        let mut properties = Map::new();
+        let mut required = vec![];
+
+        if let Some(table_name) = table_name {
+            properties.insert(
+                "id".to_string(),
+                get_record_id_schema(Some(table_name), all_tables),
+            );
+
+            if *direction == Direction::Out {
+                required.push("id".to_string());
+            }
+        }
+
+        for (name, meta) in fields {
+            if *direction != Direction::Out && meta.is_output_only() {
+                continue;
+            }
+
+            let optional = matches!(meta.r#type, FieldType::Option { .. })
+                || (*direction != Direction::Out && meta.is_set_by_database());
+
+            if !optional {
+                required.push(name.clone());
+            }
+
+            properties.insert(
+                name.clone(),
+                self.get_schema(
+                    &meta.r#type,
+                    direction,
+                    is_flexible(meta, flexible),
+                    all_tables,
+                ),
+            );
+        }
+
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": flexible,
+        })
+    }
+
+    fn get_schema(
+        &self,
+        r#type: &FieldType,
+        direction: &Direction,
+        flexible: bool,
+        all_tables: &TableMetas,
+    ) -> Value {
+        match r#type {
+            FieldType::Any => json!({}),
+            FieldType::Null => json!({ "type": "null" }),
+            FieldType::Boolean => json!({ "type": "boolean" }),
+            FieldType::Number | FieldType::Decimal => json!({ "type": "number" }),
+            FieldType::String | FieldType::Duration => json!({ "type": "string" }),
+            FieldType::Uuid => json!({ "type": "string", "format": "uuid" }),
+            FieldType::Date => json!({ "type": "string", "format": "date-time" }),
+            // The encoding of bytes depends on the protocol used to talk to the database
+            FieldType::Bytes => json!({}),
+            FieldType::Option { inner } => self.get_schema(inner, direction, flexible, all_tables),
+            FieldType::Object { fields } => match fields {
+                Some(fields) => {
+                    self.get_object_schema(fields, direction, None, flexible, all_tables)
+                }
+                None => json!({ "type": "object" }),
+            },
+            FieldType::Record { tables } => {
+                if tables.is_empty() {
+                    return match direction {
+                        Direction::In | Direction::Update => get_record_id_schema(None, all_tables),
+                        Direction::Out => json!({}),
+                    };
+                }
+
+                let variants: Vec<_> = tables
+                    .iter()
+                    .flat_map(|table| {
+                        let id = get_record_id_schema(Some(table), all_tables);
+                        let reference = json!({
+                            "$ref": format!("#/$defs/{}", create_type_name(table, direction))
+                        });
+
+                        if !is_generated(table, all_tables) {
+                            return vec![id];
+                        }
+
+                        match (direction, self.options.links_fetched) {
+                            (Direction::In | Direction::Update, _) => vec![id],
+                            (Direction::Out, true) => vec![reference],
+                            (Direction::Out, false) => vec![reference, id],
+                        }
+                    })
+                    .collect();
+
+                create_union(variants, any_of)
+            }
+            FieldType::References { tables } => {
+                let record = FieldType::Record {
+                    tables: tables.clone(),
+                };
+
+                json!({
+                    "type": "array",
+                    "items": self.get_schema(&record, direction, false, all_tables),
+                })
+            }
+            FieldType::Union(union) => match union {
+                Union::Normal { variants } => {
+                    let variants = variants
+                        .iter()
+                        .map(|variant| self.get_schema(variant, direction, flexible, all_tables))
+                        .collect();
+
+                    create_union(variants, any_of)
+                }
+                Union::Enum(r#enum) => match r#enum {
+                    Enum::String { variants } => json!({ "enum": variants }),
+                    Enum::Number { variants } => json!({ "enum": variants }),
+                },
+            },
+            FieldType::Array { item } => json!({
+                "type": "array",
+                "items": self.get_schema(item, direction, flexible, all_tables),
+            }),
+            FieldType::Literal(value) => match value {
+                Literal::String { value } => json!({ "const": value }),
+                Literal::Number { value } => json!({ "const": value }),
+                Literal::Array { items } => {
+                    let items: Vec<_> = items
+                        .iter()
+                        .map(|item| self.get_schema(item, direction, flexible, all_tables))
+                        .collect();
+
+                    json!({
+                        "type": "array",
+                        "prefixItems": items,
+                        "minItems": items.len(),
+                        "items": false,
+                    })
+                }
+            },
+        }
+    }
+}
+
+impl OutputGenerator for JsonSchemaGenerator {
+    fn name(&self) -> &str {
+        "jsonschema"
+    }
+
+    fn options(&self) -> Value {
+        options_to_value(&self.options)
+    }
+
+    fn generate(&self, schema: &Schema) -> anyhow::Result<Vec<OutputFile>> {
+        let tables = &schema.tables;
+        let mut definitions = Map::new();
+
+        for (name, meta) in tables {
+            let directions = match has_input(meta) {
+                true => vec![Direction::In, Direction::Out],
+                false => vec![Direction::Out],
+            };
+
+            for direction in directions {
+                let mut schema = self.get_object_schema(
+                    &meta.fields,
+                    &direction,
+                    Some(name),
+                    meta.schemaless,
+                    tables,
+                );
+
+                if let Some(comment) = &meta.comment {
+                    schema["description"] = json!(comment);
+                }
+
+                definitions.insert(create_type_name(name, &direction), schema);
+            }
+        }
+
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$defs": definitions,
+        });
+
+        Ok(vec![OutputFile {
+            path: self.options.output.clone(),
+            content: serde_json::to_string_pretty(&schema)?,
+        }])
+    }
+}
+
+fn get_record_id_schema(table_name: Option<&str>, all_tables: &TableMetas) -> Value {
+    match table_name {
+        Some(table) => json!({
+            "type": "string",
+            "pattern": get_record_id_pattern(table, all_tables),
+        }),
+        None => json!({ "type": "string" }),
+    }
+}
+
+fn any_of(variants: Vec<Value>) -> Value {
+    json!({ "anyOf": variants })
+}
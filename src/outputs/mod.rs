@@ -1,2 +1,288 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use convert_case::{Case, Casing};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{FieldMeta, FieldType, Schema, TableMeta, TableMetas, config::Config};
+
 pub mod db;
+pub mod ir;
+pub mod jsonschema;
+pub mod plugin;
 pub mod ts;
+pub mod zod;
+
+/// Whether a generated type describes data sent to, or received from the database
+#[derive(Debug, PartialEq)]
+pub(crate) enum Direction {
+    In,
+    Out,
+    /// Partial data sent to the database to change an existing record
+    Update,
+}
+
+/// A file produced by an output generator
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputFile {
+    pub path: String,
+    pub content: String,
+}
+
+pub trait OutputGenerator {
+    /// The name used to select the generator and to look up its options in the config
+    fn name(&self) -> &str;
+
+    /// The options the generator is running with
+    fn options(&self) -> Value;
+
+    fn generate(&self, schema: &Schema) -> anyhow::Result<Vec<OutputFile>>;
+}
+
+pub type GeneratorFactory<'a> =
+    Box<dyn Fn(&'a Config, Option<&Value>) -> anyhow::Result<Box<dyn OutputGenerator + 'a>> + 'a>;
+
+pub struct GeneratorRegistry<'a> {
+    config: &'a Config,
+    factories: BTreeMap<String, GeneratorFactory<'a>>,
+}
+
+impl<'a> GeneratorRegistry<'a> {
+    /// Creates a registry containing the built-in generators and the plugins of the config
+    pub fn new(config: &'a Config) -> anyhow::Result<Self> {
+        let mut registry = Self {
+            config,
+            factories: BTreeMap::new(),
+        };
+
+        registry.register("ts", |config, options| {
+            Ok(Box::new(ts::TSGenerator::with_options(config, options)?))
+        })?;
+        registry.register("zod", |config, options| {
+            Ok(Box::new(zod::ZodGenerator::with_options(config, options)?))
+        })?;
+        registry.register("jsonschema", |config, options| {
+            Ok(Box::new(jsonschema::JsonSchemaGenerator::with_options(
+                config, options,
+            )?))
+        })?;
+        registry.register("ir", |config, options| {
+            Ok(Box::new(ir::IrGenerator::with_options(config, options)?))
+        })?;
+
+        for spec in &config.plugins {
+            registry.register(&spec.name, move |_, options| {
+                Ok(Box::new(plugin::PluginGenerator::new(
+                    spec.clone(),
+                    options,
+                )))
+            })?;
+        }
+
+        Ok(registry)
+    }
+
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&'a Config, Option<&Value>) -> anyhow::Result<Box<dyn OutputGenerator + 'a>>
+        + 'a,
+    ) -> anyhow::Result<()> {
+        if self.factories.contains_key(name) {
+            anyhow::bail!("An output named '{name}' is already registered.");
+        }
+
+        self.factories.insert(name.to_string(), Box::new(factory));
+
+        Ok(())
+    }
+
+    /// Creates the generator registered under the name, using its option namespace from the config
+    pub fn create(&self, name: &str) -> anyhow::Result<Box<dyn OutputGenerator + 'a>> {
+        let Some(factory) = self.factories.get(name) else {
+            anyhow::bail!(
+                "Unknown output '{name}', the available outputs are: {}.",
+                self.factories
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        };
+
+        factory(self.config, self.config.outputs.get(name))
+    }
+}
+
+/// Returns the names of the outputs that should be generated based on the config
+pub fn get_selected_outputs(config: &Config) -> Vec<String> {
+    let mut selected = vec![];
+
+    let ir = config.emit_ir.as_ref().map(|_| "ir".to_string());
+
+    for name in config.emit.iter().cloned().chain(ir) {
+        if config.skip_ts_generation && name == "ts" {
+            continue;
+        }
+
+        if !selected.contains(&name) {
+            selected.push(name);
+        }
+    }
+
+    selected
+}
+
+/// Reads the options of a generator from its namespace in the config,
+/// taking every option that is not present there from the defaults
+pub fn get_options<T: Serialize + DeserializeOwned>(
+    name: &str,
+    defaults: T,
+    namespace: Option<&Value>,
+) -> anyhow::Result<T> {
+    let mut options = serde_json::to_value(defaults)?;
+
+    if let Some(namespace) = namespace {
+        let (Value::Object(options), Value::Object(overrides)) = (&mut options, namespace) else {
+            anyhow::bail!("The options of output '{name}' must be an object.");
+        };
+
+        options.extend(overrides.clone());
+    }
+
+    serde_json::from_value(options)
+        .map_err(|err| anyhow::anyhow!("Invalid options for output '{name}': {err}"))
+}
+
+/// Serializes the options of a generator, as returned by `OutputGenerator::options`
+pub(crate) fn options_to_value(options: &impl Serialize) -> Value {
+    serde_json::to_value(options).expect("The options are always serializable.")
+}
+
+/// Creates the name of the type generated for a table, like `InUser`, `UpdateUser` or `OutUser`
+pub(crate) fn create_type_name(name: &str, direction: &Direction) -> String {
+    let pascal_case_name = name.to_case(Case::Pascal);
+
+    match direction {
+        Direction::In => format!("In{pascal_case_name}"),
+        Direction::Update => format!("Update{pascal_case_name}"),
+        Direction::Out => format!("Out{pascal_case_name}"),
+    }
+}
+
+/// Combines the variants into a union, unless there is only one of them
+pub(crate) fn create_union<T>(variants: Vec<T>, union: impl FnOnce(Vec<T>) -> T) -> T {
+    match variants.len() {
+        1 => variants
+            .into_iter()
+            .next()
+            .expect("The union has one variant."),
+        _ => union(variants),
+    }
+}
+
+/// Whether a type is generated for the table, since a linked table can be filtered out
+/// of the outputs, in which case only the ids of its records can be referenced
+pub(crate) fn is_generated(table: &str, tables: &TableMetas) -> bool {
+    tables.contains_key(table)
+}
+
+/// The explicitly defined type of the id part of the record ids of a table, except when
+/// the ids are constrained to be record links, which are typed by the table name alone
+pub(crate) fn get_record_id_type<'a>(table: &str, tables: &'a TableMetas) -> Option<&'a FieldType> {
+    tables
+        .get(table)
+        .and_then(|meta| meta.record_id.as_ref())
+        .map(|record_id| &record_id.r#type)
+        .filter(|r#type| !matches!(r#type, FieldType::Record { .. }))
+}
+
+/// Creates a regex matching the string form of the record ids of a table, also checking
+/// the id part when its defined type can be told apart in that form
+pub(crate) fn get_record_id_pattern(table: &str, tables: &TableMetas) -> String {
+    let id = match get_record_id_type(table, tables) {
+        Some(FieldType::Number) => r"-?\d+$",
+        Some(FieldType::Array { .. }) => r"\[",
+        Some(FieldType::Object { .. }) => r"\{",
+        _ => "",
+    };
+
+    format!("^{}:{id}", escape_pattern(table))
+}
+
+fn escape_pattern(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match "\\^$.|?*+()[]{}/".contains(c) {
+            true => vec!['\\', c],
+            false => vec![c],
+        })
+        .collect()
+}
+
+/// Whether input types are generated for the table, views are filled by the database,
+/// so they only have output types
+pub(crate) fn has_input(table: &TableMeta) -> bool {
+    !table.view
+}
+
+/// Whether the object type of a field accepts keys that are not defined, like the ones of
+/// flexible fields and of every object nested in them or in a schemaless table
+pub(crate) fn is_flexible(field: &FieldMeta, parent_flexible: bool) -> bool {
+    field.flexible || parent_flexible
+}
+
+pub(crate) fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
+
+/// Quotes the names that are not valid identifiers, like the `math::sum` field of a view
+pub(crate) fn create_property_name(name: &str) -> String {
+    let is_identifier = !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+
+    match is_identifier {
+        true => name.to_string(),
+        false => format!("\"{name}\""),
+    }
+}
+
+pub fn write_files(files: Vec<OutputFile>) -> anyhow::Result<()> {
+    for file in files {
+        if let Some(parent) = Path::new(&file.path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        println!("Writing file '{}'...", file.path);
+        fs::write(&file.path, file.content)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_id_patterns_escape_the_table_and_check_the_id() {
+        let numbered = TableMeta {
+            record_id: Some(FieldMeta::new(FieldType::Number)),
+            ..Default::default()
+        };
+        let tables = [
+            ("numbered".to_string(), numbered),
+            ("user.v2".to_string(), TableMeta::default()),
+        ]
+        .into();
+
+        assert_eq!(
+            get_record_id_pattern("numbered", &tables),
+            r"^numbered:-?\d+$"
+        );
+        assert_eq!(get_record_id_pattern("user.v2", &tables), r"^user\.v2:");
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::{OutputFile, OutputGenerator};
+use crate::{Schema, ir::Ir};
+
+/// An external generator given as 'name=path/to/executable'
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PluginSpec {
+    pub name: String,
+    pub path: String,
+}
+
+impl TryFrom<String> for PluginSpec {
+    type Error = String;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        match spec.split_once('=') {
+            Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok(Self {
+                name: name.to_string(),
+                path: path.to_string(),
+            }),
+            _ => Err(format!(
+                "Invalid plugin '{spec}', expected the form 'name=path/to/executable'."
+            )),
+        }
+    }
+}
+
+pub fn parse_plugin_spec(spec: &str) -> Result<PluginSpec, String> {
+    PluginSpec::try_from(spec.to_string())
+}
+
+#[derive(Debug, Serialize)]
+struct PluginRequest {
+    #[serde(flatten)]
+    ir: Ir,
+    options: Value,
+}
+
+pub struct PluginGenerator {
+    spec: PluginSpec,
+    options: Value,
+}
+
+impl PluginGenerator {
+    pub fn new(spec: PluginSpec, options: Option<&Value>) -> Self {
+        Self {
+            spec,
+            options: options.cloned().unwrap_or_else(|| json!({})),
+        }
+    }
+}
+
+impl OutputGenerator for PluginGenerator {
+    fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    fn options(&self) -> Value {
+        self.options.clone()
+    }
+
+    fn generate(&self, schema: &Schema) -> anyhow::Result<Vec<OutputFile>> {
+        let PluginSpec { name, path } = &self.spec;
+
+        let request = PluginRequest {
+            ir: Ir::new(schema.clone()),
+            options: self.options(),
+        };
+        let request = serde_json::to_vec(&request)?;
+
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| anyhow::anyhow!("Failed to start plugin '{name}' at '{path}': {err}"))?;
+
+        // The request is written from a separate thread, so that a plugin that
+        // starts answering before reading all of its input can not deadlock us.
+        let mut stdin = child
+            .stdin
+            .take()
+            .expect("The stdin of the plugin is piped.");
+        let writer = thread::spawn(move || stdin.write_all(&request));
+
+        let output = child.wait_with_output()?;
+        let written = writer
+            .join()
+            .expect("The plugin input writer thread panicked.");
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            anyhow::bail!(
+                "Plugin '{name}' failed ({}):\n{}",
+                output.status,
+                stderr.trim_end()
+            );
+        }
+        if !stderr.trim().is_empty() {
+            eprintln!("[{name}] {}", stderr.trim_end());
+        }
+        written?;
+
+        serde_json::from_slice(&output.stdout)
+            .map_err(|err| anyhow::anyhow!("Plugin '{name}' returned an invalid file list: {err}"))
+    }
+}
@@ -1,66 +1,342 @@
-use std::fs::File;
 use std::io::Write;
 
 use convert_case::{Case, Casing};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    Direction, OutputFile, OutputGenerator, create_property_name, create_type_name, get_options,
+    get_record_id_type, has_input, indent, is_flexible, is_generated, options_to_value,
+};
+use crate::{
+    Enum, FieldMetas, FieldType, Literal, Schema, TableMeta, TableMetas, Union, config::Config,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TsOptions {
+    /// Path where the typescript defintion file will be generated
+    pub output: String,
+    /// Treat record types as FETCHED version of the linked table
+    pub links_fetched: bool,
+    /// Use the utility types from the JS SDK in the output file
+    pub target_sdk: bool,
+    /// The value type of the index signature added to the types of schemaless tables and flexible objects
+    pub index_signature_type: String,
+    /// Keep the types of schemaless tables and flexible objects closed, without an index signature
+    pub no_index_signatures: bool,
+    /// Skip adding the table meta descriptors to the output ts file
+    pub no_meta: bool,
+}
 
-use crate::{Enum, FieldMetas, FieldType, Literal, TableMeta, TableMetas, Union, config::Config};
-
-#[derive(Debug, PartialEq)]
-enum Direction {
-    In,
-    Out,
+impl From<&Config> for TsOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            output: config.output.clone(),
+            links_fetched: config.links_fetched,
+            target_sdk: config.target_sdk,
+            index_signature_type: config.index_signature_type.clone(),
+            no_index_signatures: config.no_index_signatures,
+            no_meta: config.no_meta,
+        }
+    }
 }
 
 pub struct TSGenerator<'a> {
     config: &'a Config,
+    options: TsOptions,
 }
 
 impl<'a> TSGenerator<'a> {
     pub fn new(config: &'a Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            options: config.into(),
+        }
+    }
+
+    /// Creates a generator that overrides the options of the config with the given ones
+    pub fn with_options(config: &'a Config, options: Option<&Value>) -> anyhow::Result<Self> {
+        Ok(Self {
+            config,
+            options: get_options("ts", config.into(), options)?,
+        })
     }
 
-    pub fn write_tables(&self, tables: &TableMetas) -> anyhow::Result<()> {
-        println!("\nWriting type declaration file...");
+    /// Writes the type declarations of the schema into any writer
+    pub fn write_schema_to(&self, schema: &Schema, out: &mut impl Write) -> anyhow::Result<()> {
+        let tables = &schema.tables;
 
-        let mut file = File::create(&self.config.output)?;
+        if self.options.target_sdk {
+            // The client is only needed by the function wrappers
+            let surreal = match schema.functions.is_empty() {
+                true => "",
+                false => ", Surreal",
+            };
 
-        if self.config.target_sdk {
             writeln!(
-                &mut file,
-                "import {{ RecordId, Duration, Uuid, Decimal }} from \"surrealdb\";\n"
+                out,
+                "import {{ RecordId, Duration, Uuid, Decimal{surreal} }} from \"surrealdb\";\n"
             )?;
         }
 
-        writeln!(file, "// ---------- TABLE TYPES ----------")?;
+        writeln!(out, "// ---------- TABLE TYPES ----------")?;
         for (name, meta) in tables {
-            let in_definition = self.get_table_definition(&name, &meta, Direction::In);
-            let out_definition = self.get_table_definition(&name, &meta, Direction::Out);
+            if has_input(meta) {
+                let in_definition = self.get_table_definition(name, meta, Direction::In, tables);
+                let update_definition =
+                    self.get_table_definition(name, meta, Direction::Update, tables);
+
+                write!(out, "{in_definition}\n\n{update_definition}\n\n")?;
+            }
+
+            let out_definition = self.get_table_definition(name, meta, Direction::Out, tables);
+            write!(out, "{out_definition}\n\n")?;
 
-            write!(file, "{in_definition}\n\n{out_definition}\n\n")?;
+            if !meta.indexes.is_empty() {
+                let indexes_name = format!("{}Indexes", name.to_case(Case::Pascal));
+                let indexes = serde_json::to_string_pretty(&meta.indexes)?;
+
+                write!(out, "export type {indexes_name} = {indexes}\n\n")?;
+            }
         }
 
-        if !self.config.no_meta {
-            writeln!(file, "// ---------- TABLE META STRUCTURE ----------")?;
+        if !schema.functions.is_empty() {
+            writeln!(out, "// ---------- FUNCTIONS ----------")?;
+            write!(out, "{}\n\n", self.get_functions_definition(schema))?;
+        }
+
+        if !schema.params.is_empty() {
+            writeln!(out, "// ---------- PARAMS ----------")?;
+            write!(out, "{}\n\n", self.get_params_definition(schema))?;
+        }
+
+        if !schema.accesses.is_empty() {
+            writeln!(out, "// ---------- ACCESS METHODS ----------")?;
+            write!(out, "{}\n\n", self.get_accesses_definition(schema))?;
+        }
+
+        // The metadata snapshots and the change feeds share the JSON patch types
+        let meta_types = self.config.store_meta_in_db || !self.options.no_meta;
+
+        if tables.values().any(|meta| meta.changefeed.is_some()) {
+            writeln!(out, "// ---------- CHANGE FEEDS ----------")?;
+            write!(out, "{}\n\n", self.get_changes_definition(tables))?;
+
+            if !meta_types {
+                writeln!(out, "{}", include_str!("../assets/json_patch_types.ts"))?;
+            }
+        }
+
+        if !self.options.no_meta {
+            writeln!(out, "// ---------- TABLE META STRUCTURE ----------")?;
             let content = serde_json::to_string_pretty(tables)?;
             write!(
-                file,
+                out,
                 "export const tables = {content} as const satisfies Record<string, TableMeta>\n\n"
             )?;
+
+            if !schema.functions.is_empty() {
+                let content = serde_json::to_string_pretty(&schema.functions)?;
+                write!(
+                    out,
+                    "export const functions = {content} as const satisfies Record<string, FunctionMeta>\n\n"
+                )?;
+            }
+
+            if !schema.params.is_empty() {
+                let content = serde_json::to_string_pretty(&schema.params)?;
+                write!(
+                    out,
+                    "export const params = {content} as const satisfies Record<string, ParamMeta>\n\n"
+                )?;
+            }
+
+            if !schema.accesses.is_empty() {
+                let content = serde_json::to_string_pretty(&schema.accesses)?;
+                write!(
+                    out,
+                    "export const accesses = {content} as const satisfies Record<string, AccessMeta>\n\n"
+                )?;
+            }
         }
 
-        if self.config.store_meta_in_db || !self.config.no_meta {
-            writeln!(file, "// ---------- TABLE META TYPES ----------")?;
-            write!(&mut file, "{}\n", include_str!("../assets/meta_types.ts"))?;
+        if meta_types {
+            writeln!(out, "// ---------- TABLE META TYPES ----------")?;
+            writeln!(out, "{}", include_str!("../assets/meta_types.ts"))?;
+            writeln!(out, "{}", include_str!("../assets/json_patch_types.ts"))?;
         }
 
         Ok(())
     }
 
-    fn get_table_definition(&self, name: &str, meta: &TableMeta, direction: Direction) -> String {
-        let interface_name = create_interface_name(name, &direction);
-        let fields = self.get_object_definition(&meta.fields, &direction, Some(name), 1);
+    fn get_functions_definition(&self, schema: &Schema) -> String {
+        let mut rows = vec!["export type Functions = {".to_string()];
+
+        for (name, meta) in &schema.functions {
+            if let Some(comment) = &meta.comment {
+                rows.push(create_doc_comment(comment, 1));
+            }
+
+            let args = meta
+                .args
+                .iter()
+                .map(|arg| {
+                    let ts_type =
+                        self.get_ts_type(&arg.r#type, &Direction::In, false, 2, &schema.tables);
+                    format!("{}: {ts_type}", arg.name)
+                })
+                .join(", ");
+
+            let returns = meta.returns.as_ref().unwrap_or(&FieldType::Any);
+            let returns = self.get_ts_type(returns, &Direction::Out, false, 2, &schema.tables);
+
+            rows.push(format!("{}\"fn::{name}\": {{", indent(1)));
+            rows.push(format!("{}args: [{args}],", indent(2)));
+            rows.push(format!("{}returns: {returns},", indent(2)));
+            rows.push(format!("{}}},", indent(1)));
+        }
+
+        rows.push("}".to_string());
+
+        if self.options.target_sdk {
+            rows.push(
+                "
+export function runFunction<F extends keyof Functions>(
+  db: Surreal,
+  name: F,
+  ...args: Functions[F][\"args\"]
+): Promise<Functions[F][\"returns\"]> {
+  return db.run<Functions[F][\"returns\"]>(name, args);
+}"
+                .to_string(),
+            );
+        }
+
+        rows.join("\n")
+    }
+
+    fn get_params_definition(&self, schema: &Schema) -> String {
+        let mut rows = vec!["export type Params = {".to_string()];
+
+        for (name, meta) in &schema.params {
+            if let Some(comment) = &meta.comment {
+                rows.push(create_doc_comment(comment, 1));
+            }
+
+            let ts_type = self.get_ts_type(&meta.r#type, &Direction::Out, false, 1, &schema.tables);
+            rows.push(format!("{}{name}: {ts_type},", indent(1)));
+        }
+
+        rows.push("}".to_string());
+
+        rows.join("\n")
+    }
+
+    fn get_accesses_definition(&self, schema: &Schema) -> String {
+        let mut rows = vec!["export type AccessParams = {".to_string()];
+
+        for (name, meta) in &schema.accesses {
+            if let Some(comment) = &meta.comment {
+                rows.push(create_doc_comment(comment, 1));
+            }
+
+            rows.push(format!("{}{name}: {{", indent(1)));
+
+            for (method, variables) in [("signup", &meta.signup), ("signin", &meta.signin)] {
+                // Access methods without the query can not be used to sign up or in
+                let variables = match variables {
+                    Some(variables) => self.get_object_definition(
+                        variables,
+                        &Direction::In,
+                        None,
+                        false,
+                        3,
+                        &schema.tables,
+                    ),
+                    None => "never".to_string(),
+                };
+
+                rows.push(format!("{}{method}: {variables},", indent(2)));
+            }
+
+            rows.push(format!("{}}},", indent(1)));
+        }
+
+        rows.push("}".to_string());
+        rows.push(
+            "
+export type SignupParams<A extends keyof AccessParams> = AccessParams[A][\"signup\"];
+
+export type SigninParams<A extends keyof AccessParams> = AccessParams[A][\"signin\"];"
+                .to_string(),
+        );
+
+        rows.join("\n")
+    }
+
+    fn get_changes_definition(&self, tables: &TableMetas) -> String {
+        let mut rows = vec![
+            "export type ChangeSet<Change> = {
+  versionstamp: number,
+  changes: Array<Change>,
+}"
+            .to_string(),
+        ];
+
+        for (name, meta) in tables {
+            let Some(changefeed) = &meta.changefeed else {
+                continue;
+            };
+
+            let pascal_case_name = name.to_case(Case::Pascal);
+            let record_interface = create_type_name(name, &Direction::Out);
+
+            let mut changes = vec![format!("{}| {{ update: {record_interface} }}", indent(1))];
+
+            // The updates of existing records come with the patches that restore the original
+            if changefeed.include_original {
+                changes.push(format!(
+                    "{}| {{ current: {record_interface}, update: Array<JsonPatchOperation> }}",
+                    indent(1)
+                ));
+            }
+
+            changes.push(format!(
+                "{}| {{ delete: {{ id: {record_interface}['id'] }} }}",
+                indent(1)
+            ));
+            changes.push(format!(
+                "{}| {{ define_table: {{ name: \"{name}\" }} }}",
+                indent(1)
+            ));
+
+            rows.push(format!(
+                "export type {pascal_case_name}Change =\n{}",
+                changes.join("\n")
+            ));
+        }
+
+        rows.join("\n\n")
+    }
+
+    fn get_table_definition(
+        &self,
+        name: &str,
+        meta: &TableMeta,
+        direction: Direction,
+        all_tables: &TableMetas,
+    ) -> String {
+        let interface_name = create_type_name(name, &direction);
+        let fields = self.get_object_definition(
+            &meta.fields,
+            &direction,
+            Some(name),
+            meta.schemaless,
+            1,
+            all_tables,
+        );
 
         format!("export type {interface_name} = {fields}")
     }
@@ -70,32 +346,69 @@ impl<'a> TSGenerator<'a> {
         fields: &FieldMetas,
         direction: &Direction,
         table_name: Option<&str>,
+        flexible: bool,
         depth: usize,
+        all_tables: &TableMetas,
     ) -> String {
         let mut rows = vec!["{".to_string()];
 
         if let Some(table_name) = table_name {
-            let record_type = match self.config.target_sdk {
-                true => format!("RecordId<\"{table_name}\">"),
-                false => "string".to_string(),
-            };
+            let record_type = self.get_record_id_ts_type(table_name, all_tables);
 
+            // The id of an existing record can not be changed
             let id = match direction {
-                Direction::In => format!("id?: {record_type},"),
-                Direction::Out => format!("id: {record_type},"),
+                Direction::In => Some(format!("id?: {record_type},")),
+                Direction::Out => Some(format!("id: {record_type},")),
+                Direction::Update => None,
             };
 
-            rows.push(format!("{}{id}", indent(depth)));
+            if let Some(id) = id {
+                rows.push(format!("{}{id}", indent(depth)));
+            }
         }
 
         for (name, meta) in fields {
+            // Readonly fields can only be set when the record is created
+            if *direction == Direction::Update && meta.readonly {
+                continue;
+            }
+
+            if *direction != Direction::Out && meta.is_output_only() {
+                continue;
+            }
+
+            // Updates are merged into the record, so every field can be left out
             let optional = matches!(meta.r#type, FieldType::Option { .. })
-                || (*direction == Direction::In && meta.has_default);
+                || *direction == Direction::Update
+                || (*direction == Direction::In && meta.is_set_by_database());
 
             let optional = if optional { "?" } else { "" };
+            let readonly =
+                match *direction == Direction::Out && (meta.readonly || meta.is_output_only()) {
+                    true => "readonly ",
+                    false => "",
+                };
+
+            let ts_type = self.get_ts_type(
+                &meta.r#type,
+                direction,
+                is_flexible(meta, flexible),
+                depth,
+                all_tables,
+            );
+            rows.push(format!(
+                "{}{readonly}{}{optional}: {ts_type},",
+                indent(depth),
+                create_property_name(name)
+            ));
+        }
 
-            let ts_type = self.get_ts_type(&meta.r#type, direction, depth);
-            rows.push(format!("{}{name}{optional}: {ts_type},", indent(depth)));
+        if flexible && !self.options.no_index_signatures {
+            rows.push(format!(
+                "{}[key: string]: {},",
+                indent(depth),
+                self.options.index_signature_type
+            ));
         }
 
         rows.push(format!("{}}}", indent(depth - 1)));
@@ -103,7 +416,28 @@ impl<'a> TSGenerator<'a> {
         rows.join("\n")
     }
 
-    fn get_ts_type(&self, r#type: &FieldType, direction: &Direction, depth: usize) -> String {
+    fn get_record_id_ts_type(&self, table_name: &str, all_tables: &TableMetas) -> String {
+        if !self.options.target_sdk {
+            return "string".to_string();
+        }
+
+        let id_type = get_record_id_type(table_name, all_tables)
+            .map(|id_type| self.get_ts_type(id_type, &Direction::Out, false, 1, all_tables));
+
+        match id_type {
+            Some(id_type) => format!("RecordId<\"{table_name}\", {id_type}>"),
+            None => format!("RecordId<\"{table_name}\">"),
+        }
+    }
+
+    fn get_ts_type(
+        &self,
+        r#type: &FieldType,
+        direction: &Direction,
+        flexible: bool,
+        depth: usize,
+        all_tables: &TableMetas,
+    ) -> String {
         match r#type {
             FieldType::Any => "any".to_string(),
             FieldType::Null => "null".to_string(),
@@ -111,40 +445,47 @@ impl<'a> TSGenerator<'a> {
             FieldType::Number => "number".to_string(),
             FieldType::String => "string".to_string(),
             FieldType::Bytes => "ArrayBuffer".to_string(),
-            FieldType::Decimal => match self.config.target_sdk {
+            FieldType::Decimal => match self.options.target_sdk {
                 true => "Decimal".to_string(),
                 false => "number".to_string(),
             },
-            FieldType::Duration => match self.config.target_sdk {
+            FieldType::Duration => match self.options.target_sdk {
                 true => "Duration".to_string(),
                 false => "string".to_string(),
             },
-            FieldType::Uuid => match self.config.target_sdk {
+            FieldType::Uuid => match self.options.target_sdk {
                 true => "Uuid".to_string(),
                 false => "string".to_string(),
             },
             FieldType::Date => match direction {
-                Direction::In => "Date | string".to_string(),
+                Direction::In | Direction::Update => "Date | string".to_string(),
                 Direction::Out => "string".to_string(),
             },
             FieldType::Option { inner } => {
-                let inner = self.get_ts_type(inner, direction, depth);
+                let inner = self.get_ts_type(inner, direction, flexible, depth, all_tables);
                 format!("{inner} | undefined")
             }
             FieldType::Object { fields } => match fields {
-                Some(fields) => self.get_object_definition(&fields, direction, None, depth + 1),
+                Some(fields) => self.get_object_definition(
+                    fields,
+                    direction,
+                    None,
+                    flexible,
+                    depth + 1,
+                    all_tables,
+                ),
                 None => "object".to_string(),
             },
             FieldType::Record { tables } => {
                 if tables.is_empty() {
-                    let record_type = match self.config.target_sdk {
+                    let record_type = match self.options.target_sdk {
                         true => "RecordId",
                         false => "string",
                     };
 
                     match direction {
-                        Direction::In => record_type.to_string(),
-                        Direction::Out => match self.config.links_fetched {
+                        Direction::In | Direction::Update => record_type.to_string(),
+                        Direction::Out => match self.options.links_fetched {
                             true => "unknown".to_string(),
                             false => format!("unknown | {record_type}"),
                         },
@@ -153,11 +494,22 @@ impl<'a> TSGenerator<'a> {
                     tables
                         .iter()
                         .map(|table| {
-                            let record_interface = create_interface_name(&table, direction);
+                            if !is_generated(table, all_tables) {
+                                return self.get_record_id_ts_type(table, all_tables);
+                            }
+
+                            let record_interface = create_type_name(table, direction);
+                            let has_input = has_input(&all_tables[table]);
 
                             match direction {
-                                Direction::In => format!("Required<{record_interface}>['id']"),
-                                Direction::Out => match self.config.links_fetched {
+                                Direction::In | Direction::Update if !has_input => {
+                                    self.get_record_id_ts_type(table, all_tables)
+                                }
+                                Direction::In | Direction::Update => {
+                                    let record_interface = create_type_name(table, &Direction::In);
+                                    format!("Required<{record_interface}>['id']")
+                                }
+                                Direction::Out => match self.options.links_fetched {
                                     true => record_interface,
                                     false => {
                                         format!("{record_interface} | {record_interface}['id']")
@@ -168,30 +520,45 @@ impl<'a> TSGenerator<'a> {
                         .join(" | ")
                 }
             }
+            FieldType::References { tables } => {
+                let record = FieldType::Record {
+                    tables: tables.clone(),
+                };
+                let record_ts_type = self.get_ts_type(&record, direction, false, depth, all_tables);
+
+                format!("Array<{record_ts_type}>")
+            }
             FieldType::Union(union) => match union {
                 Union::Normal { variants } => {
                     let ts_types: Vec<_> = variants
-                        .into_iter()
-                        .map(|variant| self.get_ts_type(variant, direction, depth))
+                        .iter()
+                        .map(|variant| {
+                            self.get_ts_type(variant, direction, flexible, depth, all_tables)
+                        })
                         .collect();
 
                     ts_types.join(" | ")
                 }
                 Union::Enum(r#enum) => match r#enum {
                     Enum::String { variants } => variants
-                        .into_iter()
+                        .iter()
                         .map(|v| format!("'{v}'"))
                         .collect::<Vec<_>>()
                         .join(" | "),
                     Enum::Number { variants } => variants
-                        .into_iter()
+                        .iter()
                         .map(|v| v.to_string())
                         .collect::<Vec<_>>()
                         .join(" | "),
                 },
             },
             FieldType::Array { item } => {
-                let item_ts_type = self.get_ts_type(item, direction, depth);
+                // Arrays are replaced as a whole on updates, so their items have to be complete
+                let direction = match direction {
+                    Direction::Update => &Direction::In,
+                    direction => direction,
+                };
+                let item_ts_type = self.get_ts_type(item, direction, flexible, depth, all_tables);
 
                 format!("Array<{item_ts_type}>")
             }
@@ -200,8 +567,8 @@ impl<'a> TSGenerator<'a> {
                 Literal::Number { value: number } => number.to_string(),
                 Literal::Array { items } => {
                     let ts_types: Vec<_> = items
-                        .into_iter()
-                        .map(|kind| self.get_ts_type(kind, direction, depth))
+                        .iter()
+                        .map(|kind| self.get_ts_type(kind, direction, flexible, depth, all_tables))
                         .collect();
 
                     format!("[{}]", ts_types.join(", "))
@@ -211,15 +578,27 @@ impl<'a> TSGenerator<'a> {
     }
 }
 
-fn create_interface_name(name: &str, direction: &Direction) -> String {
-    let pascal_case_name = name.to_case(Case::Pascal);
+impl OutputGenerator for TSGenerator<'_> {
+    fn name(&self) -> &str {
+        "ts"
+    }
+
+    fn options(&self) -> Value {
+        options_to_value(&self.options)
+    }
+
+    fn generate(&self, schema: &Schema) -> anyhow::Result<Vec<OutputFile>> {
+        let mut content = vec![];
+        self.write_schema_to(schema, &mut content)?;
 
-    match direction {
-        Direction::In => format!("In{pascal_case_name}"),
-        Direction::Out => format!("Out{pascal_case_name}"),
+        Ok(vec![OutputFile {
+            path: self.options.output.clone(),
+            content: String::from_utf8(content)?,
+        }])
     }
 }
 
-fn indent(depth: usize) -> String {
-    "  ".repeat(depth)
+/// Creates a JSDoc comment, escaping the comment terminators in the text
+fn create_doc_comment(comment: &str, depth: usize) -> String {
+    format!("{}/** {} */", indent(depth), comment.replace("*/", "*\\/"))
 }
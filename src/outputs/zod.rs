@@ -0,0 +1,305 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    Direction, OutputFile, OutputGenerator, create_property_name, create_type_name, create_union,
+    get_options, get_record_id_pattern, get_record_id_type, has_input, indent, is_flexible,
+    is_generated, options_to_value,
+};
+use crate::{
+    Enum, FieldMetas, FieldType, Literal, Schema, TableMeta, TableMetas, Union, config::Config,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ZodOptions {
+    /// Path where the zod schema file will be generated
+    pub output: String,
+    /// Treat record types as FETCHED version of the linked table
+    pub links_fetched: bool,
+    /// Validate the classes of the JS SDK instead of their string forms
+    pub target_sdk: bool,
+}
+
+pub struct ZodGenerator {
+    options: ZodOptions,
+}
+
+impl ZodGenerator {
+    pub fn with_options(config: &Config, options: Option<&Value>) -> anyhow::Result<Self> {
+        let defaults = ZodOptions {
+            output: "db.zod.ts".to_string(),
+            links_fetched: config.links_fetched,
+            target_sdk: config.target_sdk,
+        };
+
+        Ok(Self {
+            options: get_options("zod", defaults, options)?,
+        })
+    }
+
+    fn get_table_schema(
+        &self,
+        name: &str,
+        meta: &TableMeta,
+        direction: Direction,
+        all_tables: &TableMetas,
+    ) -> String {
+        let schema_name = format!("{}Schema", create_type_name(name, &direction));
+        let fields = self.get_object_schema(
+            &meta.fields,
+            &direction,
+            Some(name),
+            meta.schemaless,
+            1,
+            all_tables,
+        );
+
+        format!("export const {schema_name} = {fields};")
+    }
+
+    fn get_object_schema(
+        &self,
+        fields: &FieldMetas,
+        direction: &Direction,
+        table_name: Option<&str>,
+        flexible: bool,
+        depth: usize,
+        all_tables: &TableMetas,
+    ) -> String {
+        let mut rows = vec!["z.object({".to_string()];
+
+        if let Some(table_name) = table_name {
+            let id = self.get_record_id_schema(Some(table_name), all_tables);
+
+            let id = match direction {
+                Direction::In | Direction::Update => format!("id: {id}.optional(),"),
+                Direction::Out => format!("id: {id},"),
+            };
+
+            rows.push(format!("{}{id}", indent(depth)));
+        }
+
+        for (name, meta) in fields {
+            if *direction != Direction::Out && meta.is_output_only() {
+                continue;
+            }
+
+            let schema = self.get_zod_type(
+                &meta.r#type,
+                direction,
+                is_flexible(meta, flexible),
+                depth,
+                all_tables,
+            );
+
+            let optional = *direction != Direction::Out
+                && meta.is_set_by_database()
+                && !matches!(meta.r#type, FieldType::Option { .. });
+            let optional = if optional { ".optional()" } else { "" };
+
+            let name = create_property_name(name);
+            rows.push(format!("{}{name}: {schema}{optional},", indent(depth)));
+        }
+
+        // The keys that are not defined would be stripped from the parsed data otherwise
+        match flexible {
+            true => rows.push(format!("{}}}).passthrough()", indent(depth - 1))),
+            false => rows.push(format!("{}}})", indent(depth - 1))),
+        }
+
+        rows.join("\n")
+    }
+
+    fn get_record_id_schema(&self, table_name: Option<&str>, all_tables: &TableMetas) -> String {
+        let Some(table) = table_name else {
+            return match self.options.target_sdk {
+                true => "z.instanceof(RecordId)".to_string(),
+                false => "z.string()".to_string(),
+            };
+        };
+
+        let id_type = get_record_id_type(table, all_tables);
+
+        match (self.options.target_sdk, id_type) {
+            (true, Some(id_type)) => {
+                let id_schema = self.get_zod_type(id_type, &Direction::Out, false, 1, all_tables);
+
+                format!(
+                    "z.instanceof(RecordId).refine((id) => id.tb === \"{table}\" && {id_schema}.safeParse(id.id).success)"
+                )
+            }
+            (true, None) => {
+                format!("z.instanceof(RecordId).refine((id) => id.tb === \"{table}\")")
+            }
+            (false, Some(_)) => {
+                format!(
+                    "z.string().regex(/{}/)",
+                    get_record_id_pattern(table, all_tables)
+                )
+            }
+            (false, None) => format!("z.string().startsWith(\"{table}:\")"),
+        }
+    }
+
+    fn get_zod_type(
+        &self,
+        r#type: &FieldType,
+        direction: &Direction,
+        flexible: bool,
+        depth: usize,
+        all_tables: &TableMetas,
+    ) -> String {
+        match r#type {
+            FieldType::Any => "z.any()".to_string(),
+            FieldType::Null => "z.null()".to_string(),
+            FieldType::Boolean => "z.boolean()".to_string(),
+            FieldType::Number => "z.number()".to_string(),
+            FieldType::String => "z.string()".to_string(),
+            FieldType::Bytes => "z.instanceof(ArrayBuffer)".to_string(),
+            FieldType::Decimal => match self.options.target_sdk {
+                true => "z.instanceof(Decimal)".to_string(),
+                false => "z.number()".to_string(),
+            },
+            FieldType::Duration => match self.options.target_sdk {
+                true => "z.instanceof(Duration)".to_string(),
+                false => "z.string()".to_string(),
+            },
+            FieldType::Uuid => match self.options.target_sdk {
+                true => "z.instanceof(Uuid)".to_string(),
+                false => "z.string().uuid()".to_string(),
+            },
+            FieldType::Date => match direction {
+                Direction::In | Direction::Update => "z.union([z.date(), z.string()])".to_string(),
+                Direction::Out => "z.string()".to_string(),
+            },
+            FieldType::Option { inner } => {
+                let inner = self.get_zod_type(inner, direction, flexible, depth, all_tables);
+                format!("{inner}.optional()")
+            }
+            FieldType::Object { fields } => match fields {
+                Some(fields) => {
+                    self.get_object_schema(fields, direction, None, flexible, depth + 1, all_tables)
+                }
+                None => "z.object({}).passthrough()".to_string(),
+            },
+            FieldType::Record { tables } => {
+                let record_ids = match tables.is_empty() {
+                    true => vec![(self.get_record_id_schema(None, all_tables), true)],
+                    false => tables
+                        .iter()
+                        .map(|table| {
+                            let id = self.get_record_id_schema(Some(table), all_tables);
+                            (id, is_generated(table, all_tables))
+                        })
+                        .collect(),
+                };
+
+                let variants = record_ids
+                    .into_iter()
+                    .flat_map(|(id, can_be_fetched)| match direction {
+                        Direction::In | Direction::Update => vec![id],
+                        Direction::Out if !can_be_fetched => vec![id],
+                        Direction::Out => {
+                            // Fetched records are only checked for their id, since the
+                            // schemas of the linked tables could reference each other
+                            let fetched = format!("z.object({{ id: {id} }}).passthrough()");
+
+                            match self.options.links_fetched {
+                                true => vec![fetched],
+                                false => vec![fetched, id],
+                            }
+                        }
+                    })
+                    .collect();
+
+                create_union(variants, zod_union)
+            }
+            FieldType::References { tables } => {
+                let record = FieldType::Record {
+                    tables: tables.clone(),
+                };
+                let record_schema = self.get_zod_type(&record, direction, false, depth, all_tables);
+
+                format!("z.array({record_schema})")
+            }
+            FieldType::Union(union) => match union {
+                Union::Normal { variants } => {
+                    let variants = variants
+                        .iter()
+                        .map(|variant| {
+                            self.get_zod_type(variant, direction, flexible, depth, all_tables)
+                        })
+                        .collect();
+
+                    create_union(variants, zod_union)
+                }
+                Union::Enum(r#enum) => match r#enum {
+                    Enum::String { variants } => {
+                        let variants = variants.iter().map(|v| format!("'{v}'")).join(", ");
+                        format!("z.enum([{variants}])")
+                    }
+                    Enum::Number { variants } => {
+                        let variants = variants.iter().map(|v| format!("z.literal({v})")).collect();
+                        create_union(variants, zod_union)
+                    }
+                },
+            },
+            FieldType::Array { item } => {
+                let item_schema = self.get_zod_type(item, direction, flexible, depth, all_tables);
+
+                format!("z.array({item_schema})")
+            }
+            FieldType::Literal(value) => match value {
+                Literal::String { value: string } => format!("z.literal('{string}')"),
+                Literal::Number { value: number } => format!("z.literal({number})"),
+                Literal::Array { items } => {
+                    let items = items
+                        .iter()
+                        .map(|kind| self.get_zod_type(kind, direction, flexible, depth, all_tables))
+                        .join(", ");
+
+                    format!("z.tuple([{items}])")
+                }
+            },
+        }
+    }
+}
+
+impl OutputGenerator for ZodGenerator {
+    fn name(&self) -> &str {
+        "zod"
+    }
+
+    fn options(&self) -> Value {
+        options_to_value(&self.options)
+    }
+
+    fn generate(&self, schema: &Schema) -> anyhow::Result<Vec<OutputFile>> {
+        let tables = &schema.tables;
+        let mut imports = "import { z } from \"zod\";".to_string();
+        if self.options.target_sdk {
+            imports += "\nimport { RecordId, Duration, Uuid, Decimal } from \"surrealdb\";";
+        }
+
+        let mut sections = vec![imports];
+
+        sections.push("// ---------- TABLE SCHEMAS ----------".to_string());
+        for (name, meta) in tables {
+            if has_input(meta) {
+                sections.push(self.get_table_schema(name, meta, Direction::In, tables));
+            }
+            sections.push(self.get_table_schema(name, meta, Direction::Out, tables));
+        }
+
+        Ok(vec![OutputFile {
+            path: self.options.output.clone(),
+            content: sections.join("\n\n") + "\n",
+        }])
+    }
+}
+
+fn zod_union(variants: Vec<String>) -> String {
+    format!("z.union([{}])", variants.join(", "))
+}
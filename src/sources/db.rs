@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use serde::Deserialize;
+use surrealdb::sql::Statement;
+use surrealdb::sql::statements::{
+    DefineAccessStatement, DefineEventStatement, DefineFieldStatement, DefineFunctionStatement,
+    DefineIndexStatement, DefineParamStatement, DefineStatement,
+};
+use surrealdb::{Connection, Surreal};
+
+use super::{DefinitionSource, SchemaDefinition, TableDefinition};
+use crate::parse_sql;
+
+#[derive(Deserialize, Debug)]
+struct DatabaseInfo {
+    tables: BTreeMap<String, String>,
+    functions: BTreeMap<String, String>,
+    params: BTreeMap<String, String>,
+    accesses: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TableInfo {
+    fields: BTreeMap<String, String>,
+    indexes: BTreeMap<String, String>,
+    events: BTreeMap<String, String>,
+}
+
+/// Reads the schema of the database selected on a connection, which can be
+/// a remote instance or an embedded datastore as well
+pub struct DbSource<'a, C: Connection> {
+    db: &'a Surreal<C>,
+}
+
+impl<'a, C: Connection> DbSource<'a, C> {
+    pub fn new(db: &'a Surreal<C>) -> Self {
+        Self { db }
+    }
+
+    async fn get_table_info(&self, table: &str) -> anyhow::Result<TableInfo> {
+        let info: Option<TableInfo> = self
+            .db
+            .query(format!("INFO FOR TABLE {table}"))
+            .await?
+            .take(0)?;
+
+        Ok(info.unwrap_or_else(|| panic!("Failed to get information of table {table}.")))
+    }
+}
+
+impl<C: Connection> DefinitionSource for DbSource<'_, C> {
+    async fn get_schema_definition(&self) -> anyhow::Result<SchemaDefinition> {
+        let mut tables = vec![];
+
+        let info: Option<DatabaseInfo> = self.db.query("INFO FOR DB").await?.take(0)?;
+        let info = info.expect("Failed to get information of the database.");
+
+        let functions = get_function_definitions(info.functions)?;
+        let params = get_param_definitions(info.params)?;
+        let accesses = get_access_definitions(info.accesses)?;
+
+        let every_table = info.tables.into_values().join(";\n");
+        let result = parse_sql(&every_table)?;
+
+        for stmt in result {
+            let Statement::Define(DefineStatement::Table(table)) = stmt else {
+                panic!("Database table list contained define statement for not table.")
+            };
+
+            let info = self.get_table_info(&table.name).await?;
+            let fields = get_field_definitions(&table.name, info.fields)?;
+            let indexes = get_index_definitions(&table.name, info.indexes)?;
+            let events = get_event_definitions(&table.name, info.events)?;
+
+            tables.push(TableDefinition {
+                table,
+                fields,
+                indexes,
+                events,
+            });
+        }
+
+        Ok(SchemaDefinition {
+            tables,
+            functions,
+            params,
+            accesses,
+        })
+    }
+}
+
+fn get_field_definitions(
+    table: &str,
+    fields: BTreeMap<String, String>,
+) -> anyhow::Result<Vec<DefineFieldStatement>> {
+    let every_field = fields.into_values().join(";\n");
+
+    parse_sql(&every_field)?
+        .into_iter()
+        .map(|stmt| match stmt {
+            Statement::Define(DefineStatement::Field(field)) => Ok(field),
+            _ => panic!(
+                "The field list of table '{table}' contained define statement for not field."
+            ),
+        })
+        .collect()
+}
+
+fn get_index_definitions(
+    table: &str,
+    indexes: BTreeMap<String, String>,
+) -> anyhow::Result<Vec<DefineIndexStatement>> {
+    let every_index = indexes.into_values().join(";\n");
+
+    parse_sql(&every_index)?
+        .into_iter()
+        .map(|stmt| match stmt {
+            Statement::Define(DefineStatement::Index(index)) => Ok(index),
+            _ => panic!(
+                "The index list of table '{table}' contained define statement for not index."
+            ),
+        })
+        .collect()
+}
+
+fn get_event_definitions(
+    table: &str,
+    events: BTreeMap<String, String>,
+) -> anyhow::Result<Vec<DefineEventStatement>> {
+    let every_event = events.into_values().join(";\n");
+
+    parse_sql(&every_event)?
+        .into_iter()
+        .map(|stmt| match stmt {
+            Statement::Define(DefineStatement::Event(event)) => Ok(event),
+            _ => panic!(
+                "The event list of table '{table}' contained define statement for not event."
+            ),
+        })
+        .collect()
+}
+
+fn get_function_definitions(
+    functions: BTreeMap<String, String>,
+) -> anyhow::Result<Vec<DefineFunctionStatement>> {
+    let every_function = functions.into_values().join(";\n");
+
+    parse_sql(&every_function)?
+        .into_iter()
+        .map(|stmt| match stmt {
+            Statement::Define(DefineStatement::Function(function)) => Ok(function),
+            _ => panic!("Database function list contained define statement for not function."),
+        })
+        .collect()
+}
+
+fn get_param_definitions(
+    params: BTreeMap<String, String>,
+) -> anyhow::Result<Vec<DefineParamStatement>> {
+    let every_param = params.into_values().join(";\n");
+
+    parse_sql(&every_param)?
+        .into_iter()
+        .map(|stmt| match stmt {
+            Statement::Define(DefineStatement::Param(param)) => Ok(param),
+            _ => panic!("Database param list contained define statement for not param."),
+        })
+        .collect()
+}
+
+fn get_access_definitions(
+    accesses: BTreeMap<String, String>,
+) -> anyhow::Result<Vec<DefineAccessStatement>> {
+    let every_access = accesses.into_values().join(";\n");
+
+    parse_sql(&every_access)?
+        .into_iter()
+        .map(|stmt| match stmt {
+            Statement::Define(DefineStatement::Access(access)) => Ok(access),
+            _ => panic!("Database access list contained define statement for not access."),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use surrealdb::engine::local::Mem;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_the_definitions_of_an_embedded_datastore() {
+        let db = Surreal::new::<Mem>(()).await.expect("The datastore opens.");
+        db.use_ns("test")
+            .use_db("test")
+            .await
+            .expect("The database is selected.");
+        db.query(
+            "DEFINE TABLE user SCHEMAFULL;
+            DEFINE FIELD name ON user TYPE string;
+            DEFINE FIELD tags ON user TYPE array<string>;",
+        )
+        .await
+        .expect("The schema is defined.");
+
+        let definitions = DbSource::new(&db)
+            .get_schema_definition()
+            .await
+            .expect("The definitions can be read.")
+            .tables;
+
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].table.name.to_string(), "user");
+
+        let fields: Vec<_> = definitions[0]
+            .fields
+            .iter()
+            .map(|field| field.name.to_string())
+            .collect();
+        assert_eq!(fields, ["name", "tags", "tags[*]"]);
+    }
+}
@@ -0,0 +1,21 @@
+use super::SchemaSource;
+use crate::{Schema, ir::read_ir};
+
+/// Reads the schema from an intermediate representation JSON file
+pub struct IrSource {
+    path: String,
+}
+
+impl IrSource {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+}
+
+impl SchemaSource for IrSource {
+    async fn get_schema(&self) -> anyhow::Result<Schema> {
+        read_ir(&self.path)
+    }
+}
@@ -0,0 +1,45 @@
+use surrealdb::sql::statements::{
+    DefineAccessStatement, DefineEventStatement, DefineFieldStatement, DefineFunctionStatement,
+    DefineIndexStatement, DefineParamStatement, DefineTableStatement,
+};
+
+use crate::{Schema, get_schema};
+
+pub mod db;
+pub mod ir;
+pub mod surql;
+
+/// The definition of a table together with the definitions of its fields,
+/// ordered by field name the same way `INFO FOR TABLE` returns them, its indexes and events
+#[derive(Debug, Clone)]
+pub struct TableDefinition {
+    pub table: DefineTableStatement,
+    pub fields: Vec<DefineFieldStatement>,
+    pub indexes: Vec<DefineIndexStatement>,
+    pub events: Vec<DefineEventStatement>,
+}
+
+/// Every definition of a database, that the schema is made of
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDefinition {
+    pub tables: Vec<TableDefinition>,
+    pub functions: Vec<DefineFunctionStatement>,
+    pub params: Vec<DefineParamStatement>,
+    pub accesses: Vec<DefineAccessStatement>,
+}
+
+/// A place the schema of a database can be read from
+pub trait SchemaSource {
+    fn get_schema(&self) -> impl Future<Output = anyhow::Result<Schema>>;
+}
+
+/// A source that reads the definitions of the database, which the schema is created from
+pub trait DefinitionSource {
+    fn get_schema_definition(&self) -> impl Future<Output = anyhow::Result<SchemaDefinition>>;
+}
+
+impl<T: DefinitionSource> SchemaSource for T {
+    async fn get_schema(&self) -> anyhow::Result<Schema> {
+        Ok(get_schema(self.get_schema_definition().await?))
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use surrealdb::sql::statements::{
+    DefineAccessStatement, DefineEventStatement, DefineFieldStatement, DefineFunctionStatement,
+    DefineIndexStatement, DefineParamStatement, DefineStatement, DefineTableStatement,
+    RemoveStatement, UseStatement,
+};
+use surrealdb::sql::{Base, Kind, Part, Statement};
+
+use super::{DefinitionSource, SchemaDefinition, TableDefinition};
+use crate::parse_sql;
+
+/// Reads the schema from the DEFINE statements of SurrealQL files,
+/// without needing a running database
+pub struct SurqlSource {
+    paths: Vec<String>,
+    namespace: Option<String>,
+    database: Option<String>,
+}
+
+impl SurqlSource {
+    /// Creates a source for the given files, only keeping the statements that
+    /// are run on the given namespace and database, if those are set
+    pub fn new(paths: &[String], namespace: Option<&str>, database: Option<&str>) -> Self {
+        Self {
+            paths: paths.to_vec(),
+            namespace: namespace.map(str::to_string),
+            database: database.map(str::to_string),
+        }
+    }
+
+    fn read_schema_definition(&self) -> anyhow::Result<SchemaDefinition> {
+        let mut tables: BTreeMap<String, DefineTableStatement> = BTreeMap::new();
+        let mut fields: BTreeMap<String, BTreeMap<String, DefineFieldStatement>> = BTreeMap::new();
+        let mut indexes: BTreeMap<String, BTreeMap<String, DefineIndexStatement>> = BTreeMap::new();
+        let mut events: BTreeMap<String, BTreeMap<String, DefineEventStatement>> = BTreeMap::new();
+        let mut functions: BTreeMap<String, DefineFunctionStatement> = BTreeMap::new();
+        let mut params: BTreeMap<String, DefineParamStatement> = BTreeMap::new();
+        let mut accesses: BTreeMap<String, DefineAccessStatement> = BTreeMap::new();
+
+        let mut namespace = None;
+        let mut database = None;
+
+        for path in &self.paths {
+            println!("Reading SurrealQL file '{path}'...");
+
+            let sql = fs::read_to_string(path)?;
+
+            for stmt in parse_sql(&sql)? {
+                if let Statement::Use(UseStatement { ns, db, .. }) = &stmt {
+                    namespace = ns.clone().or(namespace);
+                    database = db.clone().or(database);
+                    continue;
+                }
+
+                let selected = is_selected(&self.namespace, &namespace)
+                    && is_selected(&self.database, &database);
+
+                if !selected {
+                    continue;
+                }
+
+                match stmt {
+                    Statement::Define(DefineStatement::Table(table)) => {
+                        let name = table.name.to_string();
+
+                        if !(table.if_not_exists && tables.contains_key(&name)) {
+                            tables.insert(name, table);
+                        }
+                    }
+                    Statement::Define(DefineStatement::Field(field)) => {
+                        let table = field.what.to_string();
+
+                        // Defining a field on a missing table creates the table
+                        tables.entry(table.clone()).or_insert_with(|| {
+                            let mut definition = DefineTableStatement::default();
+                            definition.name = field.what.clone();
+                            definition
+                        });
+
+                        let table_fields = fields.entry(table).or_default();
+                        let name = field.name.to_string();
+
+                        if field.if_not_exists && table_fields.contains_key(&name) {
+                            continue;
+                        }
+
+                        for item in get_item_definitions(&field) {
+                            table_fields.entry(item.name.to_string()).or_insert(item);
+                        }
+
+                        table_fields.insert(name, field);
+                    }
+                    Statement::Define(DefineStatement::Index(index)) => {
+                        let table_indexes = indexes.entry(index.what.to_string()).or_default();
+                        let name = index.name.to_string();
+
+                        if !(index.if_not_exists && table_indexes.contains_key(&name)) {
+                            table_indexes.insert(name, index);
+                        }
+                    }
+                    Statement::Define(DefineStatement::Event(event)) => {
+                        let table_events = events.entry(event.what.to_string()).or_default();
+                        let name = event.name.to_string();
+
+                        if !(event.if_not_exists && table_events.contains_key(&name)) {
+                            table_events.insert(name, event);
+                        }
+                    }
+                    Statement::Define(DefineStatement::Function(function)) => {
+                        let name = function.name.0.clone();
+
+                        if !(function.if_not_exists && functions.contains_key(&name)) {
+                            functions.insert(name, function);
+                        }
+                    }
+                    Statement::Define(DefineStatement::Param(param)) => {
+                        let name = param.name.0.clone();
+
+                        if !(param.if_not_exists && params.contains_key(&name)) {
+                            params.insert(name, param);
+                        }
+                    }
+                    // Only the access methods of the database are part of its schema
+                    Statement::Define(DefineStatement::Access(access))
+                        if access.base == Base::Db =>
+                    {
+                        let name = access.name.0.clone();
+
+                        if !(access.if_not_exists && accesses.contains_key(&name)) {
+                            accesses.insert(name, access);
+                        }
+                    }
+                    Statement::Remove(RemoveStatement::Table(table)) => {
+                        tables.remove(&table.name.to_string());
+                        fields.remove(&table.name.to_string());
+                        indexes.remove(&table.name.to_string());
+                        events.remove(&table.name.to_string());
+                    }
+                    Statement::Remove(RemoveStatement::Field(field)) => {
+                        if let Some(table_fields) = fields.get_mut(&field.what.to_string()) {
+                            table_fields.remove(&field.name.to_string());
+                        }
+                    }
+                    Statement::Remove(RemoveStatement::Index(index)) => {
+                        if let Some(table_indexes) = indexes.get_mut(&index.what.to_string()) {
+                            table_indexes.remove(&index.name.to_string());
+                        }
+                    }
+                    Statement::Remove(RemoveStatement::Event(event)) => {
+                        if let Some(table_events) = events.get_mut(&event.what.to_string()) {
+                            table_events.remove(&event.name.to_string());
+                        }
+                    }
+                    Statement::Remove(RemoveStatement::Function(function)) => {
+                        functions.remove(&function.name.0);
+                    }
+                    Statement::Remove(RemoveStatement::Param(param)) => {
+                        params.remove(&param.name.0);
+                    }
+                    Statement::Remove(RemoveStatement::Access(access))
+                        if access.base == Base::Db =>
+                    {
+                        accesses.remove(&access.name.0);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let tables = tables
+            .into_iter()
+            .map(|(name, table)| TableDefinition {
+                table,
+                fields: fields
+                    .remove(&name)
+                    .unwrap_or_default()
+                    .into_values()
+                    .collect(),
+                indexes: indexes
+                    .remove(&name)
+                    .unwrap_or_default()
+                    .into_values()
+                    .collect(),
+                events: events
+                    .remove(&name)
+                    .unwrap_or_default()
+                    .into_values()
+                    .collect(),
+            })
+            .collect();
+
+        Ok(SchemaDefinition {
+            tables,
+            functions: functions.into_values().collect(),
+            params: params.into_values().collect(),
+            accesses: accesses.into_values().collect(),
+        })
+    }
+}
+
+impl DefinitionSource for SurqlSource {
+    async fn get_schema_definition(&self) -> anyhow::Result<SchemaDefinition> {
+        self.read_schema_definition()
+    }
+}
+
+fn is_selected(wanted: &Option<String>, current: &Option<String>) -> bool {
+    match (wanted, current) {
+        (Some(wanted), Some(current)) => wanted == current,
+        _ => true,
+    }
+}
+
+/// Creates the `field[*]` definitions, that the database adds
+/// automatically when an array or set field is defined
+fn get_item_definitions(field: &DefineFieldStatement) -> Vec<DefineFieldStatement> {
+    let mut items = vec![];
+    let mut name = field.name.clone();
+    let mut kind = field.kind.as_ref().and_then(get_inner_kind);
+
+    while let Some(item_kind) = kind {
+        if let Kind::Any = item_kind {
+            break;
+        }
+
+        kind = get_inner_kind(&item_kind);
+        name.0.push(Part::All);
+
+        let mut item = DefineFieldStatement::default();
+        item.name = name.clone();
+        item.what = field.what.clone();
+        item.flex = field.flex;
+        item.kind = Some(item_kind);
+        item.reference = field.reference.clone();
+
+        items.push(item);
+    }
+
+    items
+}
+
+fn get_inner_kind(kind: &Kind) -> Option<Kind> {
+    match kind {
+        Kind::Option(inner) => get_inner_kind(inner),
+        Kind::Array(inner, _) | Kind::Set(inner, _) => Some(*inner.clone()),
+        Kind::Either(kinds) => {
+            let kinds: Vec<_> = kinds.iter().filter_map(get_inner_kind).collect();
+
+            match kinds.is_empty() {
+                true => None,
+                false => Some(Kind::Either(kinds)),
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::{TableMetas, get_tables_metas};
+
+    fn read_tables(
+        name: &str,
+        sql: &str,
+        namespace: Option<&str>,
+        database: Option<&str>,
+    ) -> TableMetas {
+        let path = env::temp_dir().join(format!("surreal-ts-{}-{name}.surql", std::process::id()));
+        fs::write(&path, sql).expect("The temporary file can be written.");
+
+        let paths = [path.to_string_lossy().to_string()];
+        let definition = SurqlSource::new(&paths, namespace, database).read_schema_definition();
+        fs::remove_file(&path).ok();
+
+        get_tables_metas(definition.expect("The SurrealQL is valid.").tables)
+    }
+
+    fn field_type(tables: &TableMetas, table: &str, field: &str) -> Value {
+        serde_json::to_value(&tables[table].fields[field].r#type).expect("Types are serializable.")
+    }
+
+    #[test]
+    fn keeps_only_the_statements_of_the_selected_database() {
+        let tables = read_tables(
+            "use",
+            "DEFINE TABLE unscoped;
+            USE NS app DB main;
+            DEFINE TABLE main_table;
+            USE DB other;
+            DEFINE TABLE other_table;
+            USE NS other_ns DB main;
+            DEFINE TABLE other_ns_table;",
+            Some("app"),
+            Some("main"),
+        );
+
+        assert_eq!(
+            tables.keys().collect::<Vec<_>>(),
+            ["main_table", "unscoped"]
+        );
+    }
+
+    #[test]
+    fn keeps_the_first_definition_with_if_not_exists() {
+        let tables = read_tables(
+            "if_not_exists",
+            "DEFINE TABLE user SCHEMAFULL;
+            DEFINE TABLE IF NOT EXISTS user SCHEMALESS;
+            DEFINE FIELD name ON user TYPE string;
+            DEFINE FIELD IF NOT EXISTS name ON user TYPE int;
+            DEFINE FIELD age ON user TYPE string;
+            DEFINE FIELD age ON user TYPE int;",
+            None,
+            None,
+        );
+
+        assert!(!tables["user"].schemaless);
+        assert_eq!(
+            field_type(&tables, "user", "name"),
+            json!({ "name": "string" })
+        );
+        assert_eq!(
+            field_type(&tables, "user", "age"),
+            json!({ "name": "number" })
+        );
+    }
+
+    #[test]
+    fn removes_tables_and_fields() {
+        let tables = read_tables(
+            "remove",
+            "DEFINE TABLE user;
+            DEFINE FIELD name ON user TYPE string;
+            DEFINE FIELD age ON user TYPE int;
+            REMOVE FIELD age ON user;
+            DEFINE TABLE post;
+            DEFINE FIELD title ON post TYPE string;
+            REMOVE TABLE post;
+            DEFINE TABLE post;",
+            None,
+            None,
+        );
+
+        assert_eq!(tables["user"].fields.keys().collect::<Vec<_>>(), ["name"]);
+        assert!(tables["post"].fields.is_empty());
+    }
+
+    #[test]
+    fn creates_the_table_of_a_field_implicitly() {
+        let tables = read_tables(
+            "implicit",
+            "DEFINE FIELD name ON user TYPE string;",
+            None,
+            None,
+        );
+
+        assert!(tables["user"].schemaless);
+        assert_eq!(
+            field_type(&tables, "user", "name"),
+            json!({ "name": "string" })
+        );
+    }
+
+    #[test]
+    fn synthesizes_the_item_definitions_of_arrays() {
+        let tables = read_tables(
+            "items",
+            "DEFINE FIELD tags ON post TYPE array<string>;
+            DEFINE FIELD matrix ON post TYPE option<array<array<int>>>;",
+            None,
+            None,
+        );
+
+        assert_eq!(
+            field_type(&tables, "post", "tags"),
+            json!({ "name": "array", "item": { "name": "string" } })
+        );
+        assert_eq!(
+            field_type(&tables, "post", "matrix"),
+            json!({
+                "name": "option",
+                "inner": {
+                    "name": "array",
+                    "item": { "name": "array", "item": { "name": "number" } }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn types_the_items_of_untyped_arrays_by_their_own_definition() {
+        let tables = read_tables(
+            "untyped",
+            "DEFINE FIELD anything ON post TYPE array;
+            DEFINE FIELD list ON post TYPE array;
+            DEFINE FIELD list[*] ON post TYPE int;
+            DEFINE FIELD title ON post TYPE string;",
+            None,
+            None,
+        );
+
+        assert_eq!(
+            field_type(&tables, "post", "anything"),
+            json!({ "name": "array", "item": { "name": "any" } })
+        );
+        assert_eq!(
+            field_type(&tables, "post", "list"),
+            json!({ "name": "array", "item": { "name": "number" } })
+        );
+        assert_eq!(
+            field_type(&tables, "post", "title"),
+            json!({ "name": "string" })
+        );
+    }
+
+    #[test]
+    fn resolves_the_fields_of_views() {
+        let tables = read_tables(
+            "views",
+            "DEFINE TABLE adult_names AS SELECT profile.name FROM adult;
+            DEFINE TABLE adult AS SELECT profile.age, profile.name, email AS contact FROM user WHERE profile.age >= 18;
+            DEFINE TABLE user SCHEMAFULL;
+            DEFINE FIELD email ON user TYPE string;
+            DEFINE FIELD profile ON user TYPE object;
+            DEFINE FIELD profile.name ON user TYPE string;
+            DEFINE FIELD profile.age ON user TYPE int;",
+            None,
+            None,
+        );
+
+        assert_eq!(
+            field_type(&tables, "adult", "profile"),
+            json!({ "name": "object", "fields": {
+                "age": { "type": { "name": "number" } },
+                "name": { "type": { "name": "string" } },
+            } })
+        );
+        assert_eq!(
+            field_type(&tables, "adult", "contact"),
+            json!({ "name": "string" })
+        );
+        assert_eq!(
+            field_type(&tables, "adult_names", "profile"),
+            json!({ "name": "object", "fields": {
+                "name": { "type": { "name": "string" } },
+            } })
+        );
+    }
+}